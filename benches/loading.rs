@@ -0,0 +1,46 @@
+//! Benchmarks comparing loading of owned and borrowed key-value pairs.
+//!
+//! Run with `cargo bench`, every benchmark reports the best time of several rounds.
+//! Owned and borrowed loading alternate within each round, so both are measured under the same conditions.
+
+use kivi::{load_from_string, load_from_string_ref};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of rounds every benchmark is repeated.
+const ROUNDS: usize = 20;
+
+/// Returns the time of loading the input the specified number of times, the loaded pairs are counted and dropped.
+fn measure(input: &str, count: usize, load: impl Fn(&str) -> usize) -> Duration {
+  let start = Instant::now();
+  for _ in 0..count {
+    black_box(load(black_box(input)));
+  }
+  start.elapsed()
+}
+
+/// Reports the best times of loading owned and borrowed key-value pairs from the input.
+fn compare(name: &str, input: &str, count: usize) {
+  let mut owned = Duration::MAX;
+  let mut borrowed = Duration::MAX;
+  for _ in 0..ROUNDS {
+    owned = owned.min(measure(input, count, |input| load_from_string(input).len()));
+    borrowed = borrowed.min(measure(input, count, |input| load_from_string_ref(input).len()));
+  }
+  println!(
+    "{:<12} owned {:>10.2?}   borrowed {:>10.2?}   borrowed/owned {:.2}",
+    name,
+    owned,
+    borrowed,
+    borrowed.as_secs_f64() / owned.as_secs_f64()
+  );
+}
+
+fn main() {
+  compare("small", "a\nb\n\"c\nd\"\ne\n", 20_000);
+  compare("blank lines", &"\n\n\nkey\nvalue\n\n\n".repeat(1_000), 20);
+  let large = (0..10_000)
+    .map(|i| format!("key {i}\nsome value of key {i}\n\n\"multiline\nkey {i}\"\n\"multiline\nvalue {i}\"\n\n"))
+    .collect::<String>();
+  compare("large", &large, 2);
+}
@@ -0,0 +1,166 @@
+//! # Key-value pairs borrowed from the input
+
+use crate::loader::Output;
+use crate::model::{DuplicatePolicy, KeyValuePairs};
+use crate::span::Span;
+use crate::table::Table;
+use std::borrow::Cow;
+use std::vec::IntoIter;
+
+/// A struct representing key-value pairs deserialized from KIVI format,
+/// borrowing keys and values from the input.
+///
+/// Keys and values are borrowed from the input whenever they are the same as the source text,
+/// only keys and values that had to be normalized (like multiline texts with `\r\n` line endings)
+/// are allocated.
+///
+/// Comparing key-value pairs ignores the spans of keys and values in the source text.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use kivi::load_from_string_ref;
+///
+/// let input = String::from("host\n127.0.0.1\n\"port\"\n54321\n");
+/// let kvp = load_from_string_ref(&input);
+/// assert_eq!("127.0.0.1", kvp.get("host").unwrap());
+/// assert_eq!("54321", kvp.get("port").unwrap());
+/// assert!(kvp.into_iter().all(|(key, value)| matches!((key, value), (Cow::Borrowed(_), Cow::Borrowed(_)))));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyValuePairsRef<'a> {
+  table: Table<Cow<'a, str>>,
+}
+
+impl<'a> KeyValuePairsRef<'a> {
+  /// Creates an empty set of key-value pairs.
+  pub(crate) fn new() -> Self {
+    Self { table: Table::new() }
+  }
+
+  /// Returns the value associated with the specified key.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string_ref;
+  ///
+  /// let kvp = load_from_string_ref("a\nb\n");
+  /// assert_eq!("b", kvp.get("a").unwrap());
+  /// assert_eq!(None, kvp.get("b"));
+  /// ```
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.table.get(key).map(|entry| entry.value.as_ref())
+  }
+
+  /// Returns the iterator over all values associated with the specified key, in document order.
+  ///
+  /// More than one value per key is retained only when loaded
+  /// using [DuplicatePolicy::KeepAll].
+  pub fn get_all<'b>(&'b self, key: &'b str) -> impl Iterator<Item = &'b str> {
+    self.table.get_all(key).map(|entry| entry.value.as_ref())
+  }
+
+  /// Returns the span of the specified key in the source text.
+  ///
+  /// The span of a multiline key includes markers.
+  pub fn span_of_key(&self, key: &str) -> Option<Span> {
+    self.table.get(key).and_then(|entry| entry.key_span)
+  }
+
+  /// Returns the span of the value associated with the specified key in the source text.
+  ///
+  /// The span of a multiline value includes markers.
+  pub fn span_of_value(&self, key: &str) -> Option<Span> {
+    self.table.get(key).and_then(|entry| entry.value_span)
+  }
+
+  /// Returns the iterator over comment lines attached to the specified key, see [LoaderOptions::comments](crate::LoaderOptions::comments).
+  pub fn comments(&self, key: &str) -> impl Iterator<Item = &str> {
+    self.table.get(key).into_iter().flat_map(|entry| entry.comments.iter().map(|comment| comment.as_ref()))
+  }
+
+  /// Returns [true] when the set of key-value pairs is empty.
+  pub fn is_empty(&self) -> bool {
+    self.table.len() == 0
+  }
+
+  /// Returns the number of key-value pairs.
+  ///
+  /// Duplicated keys are counted as many times as they are retained
+  /// according to [DuplicatePolicy] used while loading.
+  pub fn len(&self) -> usize {
+    self.table.len()
+  }
+
+  /// Returns the iterator over ordered keys.
+  pub fn ordered_keys(&self) -> impl Iterator<Item = &str> {
+    self.table.entries().iter().map(|entry| entry.key.as_ref())
+  }
+
+  /// Returns the iterator over ordered values.
+  pub fn ordered_values(&self) -> impl Iterator<Item = &str> {
+    self.table.entries().iter().map(|entry| entry.value.as_ref())
+  }
+
+  /// Returns the iterator over ordered key-value pairs.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string_ref;
+  ///
+  /// let kvp = load_from_string_ref("d\nc\nb\na\n");
+  /// assert_eq!("d:c,b:a", kvp.ordered_key_value_pairs().map(|(k,v)| format!("{}:{}", k,v)).collect::<Vec<String>>().join(","));
+  /// ```
+  pub fn ordered_key_value_pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.table.entries().iter().map(|entry| (entry.key.as_ref(), entry.value.as_ref()))
+  }
+
+  /// Converts borrowed key-value pairs into owned [KeyValuePairs].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{load_from_string, load_from_string_ref};
+  ///
+  /// let input = "a\nb\n\"c\r\nd\"\ne\n";
+  /// assert_eq!(load_from_string(input), load_from_string_ref(input).into_owned());
+  /// ```
+  pub fn into_owned(self) -> KeyValuePairs {
+    KeyValuePairs {
+      table: self.table.map(Cow::into_owned),
+    }
+  }
+}
+
+impl<'a> Output<'a> for KeyValuePairsRef<'a> {
+  fn contains_key(&self, key: &str) -> bool {
+    self.table.contains_key(key)
+  }
+
+  fn len(&self) -> usize {
+    self.len()
+  }
+
+  fn add(&mut self, key: Cow<'a, str>, value: Cow<'a, str>, spans: (Span, Span), comments: Vec<Cow<'a, str>>, policy: DuplicatePolicy) {
+    self.table.add(key, value, (Some(spans.0), Some(spans.1)), comments, policy);
+  }
+}
+
+impl<'a> IntoIterator for KeyValuePairsRef<'a> {
+  type Item = (Cow<'a, str>, Cow<'a, str>);
+  type IntoIter = IntoIter<(Cow<'a, str>, Cow<'a, str>)>;
+
+  /// Returns the iterator over ordered key-value pairs.
+  fn into_iter(self) -> Self::IntoIter {
+    self
+      .table
+      .into_entries()
+      .into_iter()
+      .map(|entry| (entry.key, entry.value))
+      .collect::<Vec<(Cow<'a, str>, Cow<'a, str>)>>()
+      .into_iter()
+  }
+}
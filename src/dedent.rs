@@ -0,0 +1,79 @@
+//! # Indentation stripping for multiline keys and values
+
+use crate::scanner::{CR, LF};
+
+/// Policy for stripping the indentation of lines in multiline keys and values.
+///
+/// The first line of a multiline key or value follows the opening delimiter,
+/// so only the next lines (continuation lines) are stripped. All lines of heredocs are stripped.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Dedent {
+  /// Indentation is kept verbatim.
+  #[default]
+  None,
+  /// The leading whitespaces common to all non-blank continuation lines are stripped.
+  Common,
+  /// Leading whitespaces of continuation lines are stripped up to the column
+  /// of the text following the opening delimiter, so lines aligned with the first line lose their indentation.
+  /// For heredocs, leading whitespaces are stripped up to the column of the opening line.
+  Marker,
+}
+
+/// Returns the text of a multiline key or value with stripped indentation and trimmed blank lines,
+/// or [None] when the text does not change.
+///
+/// `column` is the column of the first character of the text following the opening delimiter,
+/// for heredocs it is the column of the opening delimiter.
+pub(crate) fn reshape(text: &str, dedent: Dedent, trim_first: bool, trim_last: bool, column: usize, heredoc: bool) -> Option<String> {
+  let mut lines = split_lines(text);
+  let mut start = if heredoc { 0 } else { 1 };
+  if trim_first && lines.len() > 1 && lines[0].0.trim().is_empty() {
+    lines.remove(0);
+    start = 0;
+  }
+  if trim_last && lines.len() > 1 && lines[lines.len() - 1].0.trim().is_empty() {
+    lines.pop();
+    if let Some((_, ending)) = lines.last_mut() {
+      *ending = "";
+    }
+  }
+  let indent = match dedent {
+    Dedent::None => 0,
+    Dedent::Common => lines[start.min(lines.len())..]
+      .iter()
+      .map(|(line, _)| line)
+      .filter(|line| !line.trim().is_empty())
+      .map(|line| line.chars().take_while(|ch| ch.is_whitespace()).count())
+      .min()
+      .unwrap_or(0),
+    Dedent::Marker => column - 1,
+  };
+  let mut output = String::with_capacity(text.len());
+  for (index, (line, ending)) in lines.iter().enumerate() {
+    output.push_str(if index >= start { strip_indent(line, indent) } else { line });
+    output.push_str(ending);
+  }
+  (output != text).then_some(output)
+}
+
+/// Returns lines of the text, each line is paired with its line ending (empty for the last line).
+pub(crate) fn split_lines(text: &str) -> Vec<(&str, &str)> {
+  let mut lines = vec![];
+  let mut rest = text;
+  while let Some(index) = rest.find([LF, CR]) {
+    let len = if rest[index..].starts_with("\r\n") { 2 } else { 1 };
+    lines.push((&rest[..index], &rest[index..index + len]));
+    rest = &rest[index + len..];
+  }
+  lines.push((rest, ""));
+  lines
+}
+
+/// Returns the line without at most the specified number of leading whitespaces.
+fn strip_indent(line: &str, indent: usize) -> &str {
+  let offset = line.char_indices().take(indent).find(|(_, ch)| !ch.is_whitespace()).map(|(offset, _)| offset);
+  match offset {
+    Some(offset) => &line[offset..],
+    None => &line[line.chars().take(indent).map(char::len_utf8).sum::<usize>()..],
+  }
+}
@@ -0,0 +1,104 @@
+//! # Delimiters of multiline keys and values
+
+use crate::scanner::{CR, LF};
+
+/// Pair of opening and closing delimiters enclosing a multiline key or value.
+///
+/// A marker is a delimiter with the same single character used for opening and closing.
+/// Asymmetric delimiters like `[`/`]` or `<<<`/`>>>` are configured using
+/// [LoaderOptions::delimiters](crate::LoaderOptions::delimiters).
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{Event, LoaderOptions};
+///
+/// let options = LoaderOptions::new().delimiters(&[("<<<", ">>>")]);
+/// let Some(Event::Key { delimiter: Some(delimiter), .. }) = options.parse_str("<<<a\nb>>>\nc\n").next() else { panic!() };
+/// assert_eq!("<<<", delimiter.open());
+/// assert_eq!(">>>", delimiter.close());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Delimiter {
+  open: String,
+  close: String,
+  heredoc: bool,
+}
+
+impl Delimiter {
+  /// Creates a pair of delimiters.
+  pub(crate) fn new(open: &str, close: &str) -> Self {
+    Self {
+      open: open.to_string(),
+      close: close.to_string(),
+      heredoc: false,
+    }
+  }
+
+  /// Creates an empty delimiter, standing for no multiline key or value being open.
+  pub(crate) fn none() -> Self {
+    Self {
+      open: String::new(),
+      close: String::new(),
+      heredoc: false,
+    }
+  }
+
+  /// Creates a heredoc delimiter, opened by `<<` followed by the terminator and closed by the terminator.
+  pub(crate) fn heredoc(terminator: &str) -> Self {
+    Self {
+      open: format!("<<{}", terminator),
+      close: terminator.to_string(),
+      heredoc: true,
+    }
+  }
+
+  /// Creates a marker, using the same character for opening and closing.
+  pub(crate) fn marker(marker: char) -> Self {
+    let marker = marker.to_string();
+    Self::new(&marker, &marker)
+  }
+
+  /// Returns delimiters created from pairs of opening and closing delimiters,
+  /// skipping empty delimiters and delimiters containing line endings.
+  pub(crate) fn from_pairs(pairs: &[(&str, &str)]) -> Vec<Self> {
+    pairs
+      .iter()
+      .filter(|(open, close)| is_valid(open) && is_valid(close))
+      .map(|(open, close)| Self::new(open, close))
+      .collect()
+  }
+
+  /// Returns markers created from characters, skipping line endings.
+  pub(crate) fn from_markers(markers: &[char]) -> Vec<Self> {
+    markers
+      .iter()
+      .filter(|marker| **marker != LF && **marker != CR)
+      .map(|marker| Self::marker(*marker))
+      .collect()
+  }
+
+  /// Returns the opening delimiter.
+  pub fn open(&self) -> &str {
+    &self.open
+  }
+
+  /// Returns the closing delimiter.
+  pub fn close(&self) -> &str {
+    &self.close
+  }
+
+  /// Returns `true` for heredoc delimiters, see [LoaderOptions::heredoc](crate::LoaderOptions::heredoc).
+  ///
+  /// Heredoc opening delimiter and terminator are placed in separate lines,
+  /// the line ending following the opening delimiter and the line ending preceding the terminator
+  /// are not a part of the key or value.
+  pub fn is_heredoc(&self) -> bool {
+    self.heredoc
+  }
+}
+
+/// Returns `true` when the delimiter is not empty and contains no line endings.
+fn is_valid(delimiter: &str) -> bool {
+  !delimiter.is_empty() && !delimiter.contains([LF, CR])
+}
@@ -0,0 +1,77 @@
+//! # Diagnostics reported while loading KIVI documents
+
+use crate::errors::ErrorKind;
+use crate::span::Span;
+use std::fmt::{Display, Formatter};
+
+/// Severity of a diagnostic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+  /// Part of the input was dropped, loaded key-value pairs differ from the document.
+  Error,
+  /// The input is suspicious, but all key-value pairs were loaded.
+  Warning,
+}
+
+impl Display for Severity {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Severity::Error => write!(f, "error"),
+      Severity::Warning => write!(f, "warning"),
+    }
+  }
+}
+
+/// A problem detected in the input that did not prevent loading the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  severity: Severity,
+  kind: ErrorKind,
+  span: Span,
+}
+
+impl Diagnostic {
+  /// Creates a diagnostic with [Severity::Error].
+  pub(crate) fn error(kind: ErrorKind, span: Span) -> Self {
+    Self {
+      severity: Severity::Error,
+      kind,
+      span,
+    }
+  }
+
+  /// Creates a diagnostic with [Severity::Warning].
+  pub(crate) fn warning(kind: ErrorKind, span: Span) -> Self {
+    Self {
+      severity: Severity::Warning,
+      kind,
+      span,
+    }
+  }
+
+  /// Returns the severity of this diagnostic.
+  pub fn severity(&self) -> Severity {
+    self.severity
+  }
+
+  /// Returns the kind of the detected problem.
+  pub fn kind(&self) -> &ErrorKind {
+    &self.kind
+  }
+
+  /// Returns the stable code of the detected problem, see [ErrorKind::code].
+  pub fn code(&self) -> &'static str {
+    self.kind.code()
+  }
+
+  /// Returns the span of the source text the problem was detected in.
+  pub fn span(&self) -> Span {
+    self.span
+  }
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}[{}]: {} at {}", self.severity, self.code(), self.kind, self.span.start())
+  }
+}
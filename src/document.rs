@@ -0,0 +1,410 @@
+//! # Lossless representation of KIVI documents
+
+use crate::delimiter::Delimiter;
+use crate::errors::{Error, ErrorKind, Result};
+use crate::line_ending::LineEnding;
+use crate::model::{DuplicatePolicy, KeyValuePairs};
+use crate::options::LoaderOptions;
+use crate::parser::Event;
+use crate::scanner::{BOM, CR, LF};
+use crate::span::Span;
+use crate::writer::{format_raw, Style};
+use std::fmt::{Display, Formatter};
+
+/// Key or value as it appears in the source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+  /// Source text of the key or value, including delimiters.
+  raw: String,
+  /// Text of the key or value.
+  text: String,
+  /// Delimiters enclosing the multiline key or value.
+  delimiter: Option<Delimiter>,
+  /// Span in the source text, not available for changed keys and values.
+  span: Option<Span>,
+}
+
+/// Key-value pair as it appears in the source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pair {
+  key: Node,
+  /// Source text between the key and the value.
+  separator: String,
+  value: Node,
+  /// Comment lines attached to the key.
+  comments: Vec<String>,
+  /// Length of the end of the preceding source text holding the comment lines attached to the key,
+  /// together with the indentation of the key.
+  attached: usize,
+}
+
+/// A KIVI document preserving every byte of the source text.
+///
+/// Besides keys and values, the document retains everything the loader skips:
+/// blank lines, indentation, chosen delimiters, line endings and malformed input.
+/// Writing an unchanged document gives back exactly the source text,
+/// changing a value modifies only the source text of that value.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::Document;
+///
+/// let mut document = Document::parse("host\r\n  127.0.0.1\r\n\r\n\"port\"\r\n54321\r\n");
+/// assert_eq!("127.0.0.1", document.get("host").unwrap());
+/// document.set("host", "0.0.0.0").unwrap();
+/// document.insert("timeout", "12ms").unwrap();
+/// assert_eq!("host\r\n  0.0.0.0\r\n\r\n\"port\"\r\n54321\r\ntimeout\r\n12ms\r\n", document.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+  /// Source text preceding each key-value pair, the last item is the source text following the last key-value pair.
+  trivia: Vec<String>,
+  /// Key-value pairs in document order.
+  pairs: Vec<Pair>,
+  /// Delimiters allowed in multiline keys.
+  key_delimiters: Vec<Delimiter>,
+  /// Delimiters allowed in multiline values.
+  value_delimiters: Vec<Delimiter>,
+  /// Policy for duplicated keys.
+  duplicates: DuplicatePolicy,
+  /// Style of changed keys, the line ending is also used for added line breaks.
+  key_style: Style,
+  /// Style of changed values.
+  value_style: Style,
+  /// Problem at the end of the source text that would swallow appended key-value pairs:
+  /// a key without value or an unterminated multiline key or value.
+  incomplete: Option<Error>,
+}
+
+impl Document {
+  /// Parses the document using default options.
+  ///
+  /// Malformed input is retained in the document, but is not reported.
+  /// Use [LoaderOptions::load_document] to parse using custom options or to report malformed input.
+  pub fn parse(input: &str) -> Self {
+    LoaderOptions::shared().load_document_with_diagnostics(input).0
+  }
+
+  /// Creates a document from the source text and the events reported by the parser.
+  pub(crate) fn from_events<'a>(input: &str, events: impl Iterator<Item = Event<'a>>, options: &LoaderOptions) -> Self {
+    let key_style = Style {
+      line_ending: LineEnding::detect(input).unwrap_or_default(),
+      escapes: options.escapes,
+      preserve_line_endings: options.preserve_line_endings,
+      comments: options.comments.clone(),
+      heredoc: options.heredoc,
+      dedent: options.dedent,
+      trim_first_blank_line: options.trim_first_blank_line,
+      trim_last_blank_line: options.trim_last_blank_line,
+      trim: options.key_trim,
+      empty_value: None,
+    };
+    let value_style = Style {
+      trim: options.value_trim,
+      empty_value: options.empty_value.clone(),
+      ..key_style.clone()
+    };
+    let mut document = Self {
+      trivia: vec![],
+      pairs: vec![],
+      key_delimiters: options.key_delimiters.clone(),
+      value_delimiters: options.value_delimiters.clone(),
+      duplicates: options.duplicates,
+      key_style,
+      value_style,
+      incomplete: None,
+    };
+    // Offset of the first character following the last key-value pair.
+    let mut cursor = 0;
+    let mut key: Option<(Node, Span)> = None;
+    let mut comments = vec![];
+    // Offset of the line starting the comments attached to the next key.
+    let mut comments_start = None;
+    for event in events {
+      match event {
+        Event::Key { text, span, delimiter, .. } => {
+          let node = Node {
+            raw: input[span.range()].to_string(),
+            text: text.into_owned(),
+            delimiter,
+            span: Some(span),
+          };
+          key = Some((node, span));
+        }
+        Event::Value { text, span, delimiter, .. } => {
+          if let Some((key, key_span)) = key.take() {
+            document.trivia.push(input[cursor..key_span.start().offset()].to_string());
+            document.pairs.push(Pair {
+              key,
+              separator: input[key_span.end().offset()..span.start().offset()].to_string(),
+              value: Node {
+                raw: input[span.range()].to_string(),
+                text: text.into_owned(),
+                delimiter,
+                span: Some(span),
+              },
+              comments: std::mem::take(&mut comments),
+              attached: comments_start.take().map_or(0, |start| key_span.start().offset() - start),
+            });
+            cursor = span.end().offset();
+            document.incomplete = None;
+          }
+        }
+        Event::Error {
+          kind: kind @ (ErrorKind::MissingValue | ErrorKind::UnterminatedKey | ErrorKind::UnterminatedValue),
+          span,
+        } => document.incomplete = Some(Error::new(kind, span.start())),
+        Event::Comment { text, span } => {
+          if key.is_none() && comments_start.is_none() {
+            let start = span.start().offset();
+            comments_start = Some(input[..start].rfind([LF, CR]).map_or(0, |index| index + 1).max(cursor));
+          }
+          comments.push(text.into_owned());
+        }
+        Event::BlankLine { .. } if key.is_none() => {
+          comments.clear();
+          comments_start = None;
+        }
+        _ => {}
+      }
+    }
+    document.trivia.push(input[cursor..].to_string());
+    document
+  }
+
+  /// Returns the position of the key-value pair providing the value of the specified key,
+  /// according to the policy for duplicated keys.
+  fn position(&self, key: &str) -> Option<usize> {
+    let mut positions = self.pairs.iter().enumerate().filter(|(_, pair)| pair.key.text == key).map(|(index, _)| index);
+    match self.duplicates {
+      DuplicatePolicy::FirstWins | DuplicatePolicy::Reject => positions.next(),
+      DuplicatePolicy::LastWins | DuplicatePolicy::KeepAll => positions.next_back(),
+    }
+  }
+
+  /// Returns the value associated with the specified key.
+  ///
+  /// When the key occurs more than once, the value is chosen according to
+  /// the policy for duplicated keys used while parsing the document.
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.position(key).map(|index| self.pairs[index].value.text.as_str())
+  }
+
+  /// Returns the iterator over comment lines attached to the specified key,
+  /// see [KeyValuePairs::comments].
+  pub fn comments(&self, key: &str) -> impl Iterator<Item = &str> {
+    self.position(key).into_iter().flat_map(|index| self.pairs[index].comments.iter().map(String::as_str))
+  }
+
+  /// Returns the style of line endings used in the document, the style of the first line ending in the source text.
+  ///
+  /// Added line breaks use the same style. Documents without line endings use [LineEnding::Lf].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{Document, LineEnding};
+  ///
+  /// assert_eq!(LineEnding::CrLf, Document::parse("a\r\nb\r\n").line_ending());
+  /// assert_eq!(LineEnding::Lf, Document::parse("").line_ending());
+  /// ```
+  pub fn line_ending(&self) -> LineEnding {
+    self.key_style.line_ending
+  }
+
+  /// Returns [true] when the document contains no key-value pairs.
+  pub fn is_empty(&self) -> bool {
+    self.pairs.is_empty()
+  }
+
+  /// Returns the number of key-value pairs in the document, including duplicated keys.
+  pub fn len(&self) -> usize {
+    self.pairs.len()
+  }
+
+  /// Returns the iterator over all key-value pairs, in document order.
+  pub fn key_value_pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.pairs.iter().map(|pair| (pair.key.text.as_str(), pair.value.text.as_str()))
+  }
+
+  /// Sets the value associated with the specified key, only when the key is present.
+  ///
+  /// Only the source text of the value is replaced, the delimiter of a multiline value is kept
+  /// whenever possible. Returns `false` when the key is not present.
+  /// Fails with [ErrorKind::Unrepresentable](crate::ErrorKind::Unrepresentable)
+  /// when none of the value delimiters allowed in the document keeps the value unchanged when loaded back.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::Document;
+  ///
+  /// let mut document = Document::parse("a\n  b  \n\n\"c\"\n\"d\ne\"\n");
+  /// assert!(document.set("a", "f").unwrap());
+  /// assert!(document.set("c", "g").unwrap());
+  /// assert!(!document.set("h", "i").unwrap());
+  /// assert_eq!("a\n  f  \n\n\"c\"\n\"g\"\n", document.to_string());
+  /// ```
+  pub fn set(&mut self, key: &str, value: &str) -> Result<bool> {
+    let Some(index) = self.position(key) else {
+      return Ok(false);
+    };
+    let preferred = self.pairs[index].value.delimiter.as_ref();
+    let node = self.create_node(value, &self.value_delimiters, &self.value_style, preferred, self.value_column(index))?;
+    if node.delimiter.is_some() {
+      self.break_line(index + 1);
+    }
+    self.pairs[index].value = node;
+    Ok(true)
+  }
+
+  /// Sets the value associated with the specified key.
+  ///
+  /// When the key is present, its value is replaced like by [set](Self::set).
+  /// Otherwise, the key-value pair is appended at the end of the document.
+  /// Appending fails with the error reported while parsing, when the document ends with a key without value
+  /// or with an unterminated multiline key or value, since the appended key-value pair would not be loaded back.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{Document, ErrorKind};
+  ///
+  /// let mut document = Document::parse("a\nb\nc\n");
+  /// assert_eq!(&ErrorKind::MissingValue, document.insert("d", "e").unwrap_err().kind());
+  /// assert_eq!("a\nb\nc\n", document.to_string());
+  /// ```
+  pub fn insert(&mut self, key: &str, value: &str) -> Result<()> {
+    if self.set(key, value)? {
+      return Ok(());
+    }
+    if let Some(error) = &self.incomplete {
+      return Err(error.clone());
+    }
+    let key = self.create_node(key, &self.key_delimiters, &self.key_style, None, 1)?;
+    let value = self.create_node(value, &self.value_delimiters, &self.value_style, None, 1)?;
+    let line_ending = self.key_style.line_ending.as_str();
+    if !self.ends_with_line_ending() {
+      self.trivia.last_mut().unwrap().push_str(line_ending);
+    }
+    self.pairs.push(Pair {
+      key,
+      separator: line_ending.to_string(),
+      value,
+      comments: vec![],
+      attached: 0,
+    });
+    self.trivia.push(line_ending.to_string());
+    Ok(())
+  }
+
+  /// Removes all occurrences of the specified key, together with their lines and attached comment lines.
+  ///
+  /// Returns `false` when the key is not present.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::Document;
+  ///
+  /// let mut document = Document::parse("a\nb\n\n  c\n  d\n\ne\nf\n");
+  /// assert!(document.remove("c"));
+  /// assert!(!document.remove("c"));
+  /// assert_eq!("a\nb\n\n\ne\nf\n", document.to_string());
+  /// ```
+  pub fn remove(&mut self, key: &str) -> bool {
+    let len = self.pairs.len();
+    for index in (0..len).rev() {
+      if self.pairs[index].key.text == key {
+        self.remove_pair(index);
+      }
+    }
+    self.pairs.len() != len
+  }
+
+  /// Returns key-value pairs of the document,
+  /// resolving duplicated keys according to the policy used while parsing the document.
+  ///
+  /// Spans are available for keys and values not changed after parsing.
+  pub fn to_key_value_pairs(&self) -> KeyValuePairs {
+    let mut kvp = KeyValuePairs::new();
+    for pair in &self.pairs {
+      kvp.add_owned(
+        pair.key.text.clone(),
+        pair.value.text.clone(),
+        (pair.key.span, pair.value.span),
+        pair.comments.clone(),
+        self.duplicates,
+      );
+    }
+    kvp
+  }
+
+  /// Creates a node with the source text of the specified key or value.
+  fn create_node(&self, text: &str, delimiters: &[Delimiter], style: &Style, preferred: Option<&Delimiter>, column: usize) -> Result<Node> {
+    let (raw, delimiter) = format_raw(text, delimiters, preferred, style, column)?;
+    Ok(Node {
+      raw,
+      text: text.to_string(),
+      delimiter,
+      span: None,
+    })
+  }
+
+  /// Returns the column of the first character of the value at the specified position.
+  fn value_column(&self, index: usize) -> usize {
+    let pair = &self.pairs[index];
+    let preceding = format!("{}{}{}", self.trivia[index], pair.key.raw, pair.separator);
+    let line = preceding.rsplit([LF, CR]).next().unwrap_or_default();
+    line.trim_start_matches(BOM).chars().count() + 1
+  }
+
+  /// Makes sure the closing delimiter preceding the specified trivia is followed by a line ending.
+  fn break_line(&mut self, index: usize) {
+    let line_ending = self.key_style.line_ending.as_str();
+    let trivia = &mut self.trivia[index];
+    let rest = trivia.trim_start_matches(|ch: char| ch.is_whitespace() && ch != LF && ch != CR);
+    *trivia = if rest.starts_with([LF, CR]) {
+      rest.to_string()
+    } else {
+      format!("{}{}", line_ending, rest)
+    };
+  }
+
+  /// Returns `true` when the source text of the document is empty or ends with a line ending.
+  fn ends_with_line_ending(&self) -> bool {
+    let trailing = self.trivia.last().unwrap();
+    if trailing.is_empty() {
+      self.pairs.is_empty() && self.trivia.len() == 1
+    } else {
+      trailing.ends_with([LF, CR])
+    }
+  }
+
+  /// Removes the key-value pair at the specified position, together with the attached comment lines,
+  /// the indentation of the key and the line ending following the value.
+  fn remove_pair(&mut self, index: usize) {
+    let pair = self.pairs.remove(index);
+    let following = self.trivia.remove(index + 1);
+    let preceding = &mut self.trivia[index];
+    preceding.truncate(preceding.len() - pair.attached);
+    let indented = preceding.trim_end_matches([' ', '\t']).len();
+    if preceding[..indented].is_empty() || preceding[..indented].ends_with([LF, CR]) {
+      preceding.truncate(indented);
+    }
+    let rest = following.trim_start_matches([' ', '\t']);
+    let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix([LF, CR])).unwrap_or(rest);
+    preceding.push_str(rest);
+  }
+}
+
+impl Display for Document {
+  /// Writes the source text of the document.
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for (trivia, pair) in self.trivia.iter().zip(self.pairs.iter()) {
+      write!(f, "{}{}{}{}", trivia, pair.key.raw, pair.separator, pair.value.raw)?;
+    }
+    write!(f, "{}", self.trivia.last().unwrap())
+  }
+}
@@ -0,0 +1,207 @@
+//! # Errors reported while loading KIVI documents
+
+use crate::span::Position;
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+/// Result type returned by fallible KIVI functions.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Kinds of problems detected in KIVI documents.
+///
+/// New kinds of problems may be added in future releases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+  /// Multiline key is not closed with a marker followed by a newline.
+  UnterminatedKey,
+  /// Multiline value is not closed with a marker followed by a newline.
+  UnterminatedValue,
+  /// Unknown or malformed escape sequence in a multiline key or value.
+  InvalidEscape,
+  /// Key is not followed by any value.
+  MissingValue,
+  /// Marker that probably was meant to close a multiline key or value
+  /// is followed by other characters in the same line.
+  TextAfterMarker,
+  /// The same key occurs more than once.
+  DuplicateKey,
+  /// Key is longer than allowed.
+  KeyTooLong,
+  /// Value is longer than allowed.
+  ValueTooLong,
+  /// Document contains more key-value pairs than allowed.
+  TooManyPairs,
+  /// Reading the input or writing the output failed, with the kind of the I/O error.
+  ///
+  /// The I/O error itself is available as the [source](std::error::Error::source) of [Error].
+  Io(io::ErrorKind),
+  /// Key or value can not be written in KIVI format, so that it is loaded back unchanged.
+  Unrepresentable,
+}
+
+impl ErrorKind {
+  /// Returns the stable code of this kind of problem.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::ErrorKind;
+  ///
+  /// assert_eq!("unterminated-key", ErrorKind::UnterminatedKey.code());
+  /// assert_eq!("duplicate-key", ErrorKind::DuplicateKey.code());
+  /// ```
+  pub fn code(&self) -> &'static str {
+    match self {
+      ErrorKind::UnterminatedKey => "unterminated-key",
+      ErrorKind::UnterminatedValue => "unterminated-value",
+      ErrorKind::InvalidEscape => "invalid-escape",
+      ErrorKind::MissingValue => "missing-value",
+      ErrorKind::TextAfterMarker => "text-after-marker",
+      ErrorKind::DuplicateKey => "duplicate-key",
+      ErrorKind::KeyTooLong => "key-too-long",
+      ErrorKind::ValueTooLong => "value-too-long",
+      ErrorKind::TooManyPairs => "too-many-pairs",
+      ErrorKind::Io(_) => "io",
+      ErrorKind::Unrepresentable => "unrepresentable",
+    }
+  }
+}
+
+impl Display for ErrorKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ErrorKind::UnterminatedKey => write!(f, "unterminated multiline key"),
+      ErrorKind::UnterminatedValue => write!(f, "unterminated multiline value"),
+      ErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+      ErrorKind::MissingValue => write!(f, "missing value"),
+      ErrorKind::TextAfterMarker => write!(f, "text after closing marker"),
+      ErrorKind::DuplicateKey => write!(f, "duplicate key"),
+      ErrorKind::KeyTooLong => write!(f, "key too long"),
+      ErrorKind::ValueTooLong => write!(f, "value too long"),
+      ErrorKind::TooManyPairs => write!(f, "too many key-value pairs"),
+      ErrorKind::Io(kind) => write!(f, "I/O error: {}", kind),
+      ErrorKind::Unrepresentable => write!(f, "key or value can not be represented in KIVI format"),
+    }
+  }
+}
+
+/// An error reported while loading a KIVI document.
+///
+/// Lines and columns are counted from 1, columns are counted in characters.
+/// Errors not related to any place in the input (like I/O errors)
+/// report line and column equal to 0.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{try_load_from_file, ErrorKind};
+/// use std::error::Error;
+/// use std::io;
+///
+/// let err = try_load_from_file("non-existing.kivi").unwrap_err();
+/// assert_eq!(&ErrorKind::Io(io::ErrorKind::NotFound), err.kind());
+/// let source = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+/// assert_eq!(io::ErrorKind::NotFound, source.kind());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Error {
+  kind: ErrorKind,
+  position: Option<Position>,
+  /// I/O error that caused this error.
+  source: Option<IoSource>,
+}
+
+/// I/O error that caused an [Error], shared by clones of the error.
+///
+/// The I/O error is never accessed mutably, so it is safe to observe after a panic,
+/// even though the custom error boxed in [io::Error] is not required to be unwind safe.
+#[derive(Clone)]
+struct IoSource(Arc<io::Error>);
+
+impl UnwindSafe for IoSource {}
+
+impl RefUnwindSafe for IoSource {}
+
+impl Debug for IoSource {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    Debug::fmt(&self.0, f)
+  }
+}
+
+impl Error {
+  /// Creates a new error of the specified kind reported at the specified position.
+  pub(crate) fn new(kind: ErrorKind, position: Position) -> Self {
+    Self {
+      kind,
+      position: Some(position),
+      source: None,
+    }
+  }
+
+  /// Creates a new error of the specified kind, not related to any place in the input.
+  pub(crate) fn unpositioned(kind: ErrorKind) -> Self {
+    Self {
+      kind,
+      position: None,
+      source: None,
+    }
+  }
+
+  /// Returns the kind of this error.
+  pub fn kind(&self) -> &ErrorKind {
+    &self.kind
+  }
+
+  /// Returns the position where the error was detected, if related to any place in the input.
+  pub fn position(&self) -> Option<Position> {
+    self.position
+  }
+
+  /// Returns the line number where the error was detected.
+  pub fn line(&self) -> usize {
+    self.position.map(|position| position.line()).unwrap_or_default()
+  }
+
+  /// Returns the column number where the error was detected.
+  pub fn column(&self) -> usize {
+    self.position.map(|position| position.column()).unwrap_or_default()
+  }
+}
+
+impl PartialEq for Error {
+  /// Errors are equal when their kinds and positions are equal, I/O errors are compared by their kinds.
+  fn eq(&self, other: &Self) -> bool {
+    self.kind == other.kind && self.position == other.position
+  }
+}
+
+impl Eq for Error {}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match (&self.source, self.position) {
+      (Some(IoSource(source)), _) => write!(f, "I/O error: {}", source),
+      (None, Some(position)) => write!(f, "{} at {}", self.kind, position),
+      (None, None) => write!(f, "{}", self.kind),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    self.source.as_ref().map(|IoSource(e)| e.as_ref() as &(dyn std::error::Error + 'static))
+  }
+}
+
+impl From<io::Error> for Error {
+  fn from(e: io::Error) -> Self {
+    Self {
+      kind: ErrorKind::Io(e.kind()),
+      position: None,
+      source: Some(IoSource(Arc::new(e))),
+    }
+  }
+}
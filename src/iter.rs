@@ -0,0 +1,184 @@
+//! # Iterators over key-value pairs
+
+use crate::table::{Entry, PrimaryEntries};
+use std::fmt::{Debug, Formatter};
+use std::iter::FusedIterator;
+use std::{slice, vec};
+
+/// Iterator over the keys of [KeyValuePairs](crate::KeyValuePairs), each key is returned once.
+///
+/// Returned by [KeyValuePairs::keys](crate::KeyValuePairs::keys).
+#[derive(Debug, Clone)]
+pub struct Keys<'a> {
+  entries: PrimaryEntries<'a, String>,
+}
+
+impl<'a> Keys<'a> {
+  /// Creates an iterator over the keys of the specified entries.
+  pub(crate) fn new(entries: PrimaryEntries<'a, String>) -> Self {
+    Self { entries }
+  }
+}
+
+impl<'a> Iterator for Keys<'a> {
+  type Item = &'a String;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.entries.next().map(|entry| &entry.key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl ExactSizeIterator for Keys<'_> {}
+
+impl FusedIterator for Keys<'_> {}
+
+/// Iterator over the values of [KeyValuePairs](crate::KeyValuePairs), one value per key.
+///
+/// Returned by [KeyValuePairs::values](crate::KeyValuePairs::values).
+#[derive(Debug, Clone)]
+pub struct Values<'a> {
+  entries: PrimaryEntries<'a, String>,
+}
+
+impl<'a> Values<'a> {
+  /// Creates an iterator over the values of the specified entries.
+  pub(crate) fn new(entries: PrimaryEntries<'a, String>) -> Self {
+    Self { entries }
+  }
+}
+
+impl<'a> Iterator for Values<'a> {
+  type Item = &'a String;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.entries.next().map(|entry| &entry.value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl ExactSizeIterator for Values<'_> {}
+
+impl FusedIterator for Values<'_> {}
+
+/// Iterator over the keys of [KeyValuePairs](crate::KeyValuePairs), in document order.
+///
+/// Returned by [KeyValuePairs::ordered_keys](crate::KeyValuePairs::ordered_keys).
+#[derive(Debug, Clone)]
+pub struct OrderedKeys<'a> {
+  entries: slice::Iter<'a, Entry<String>>,
+}
+
+impl<'a> OrderedKeys<'a> {
+  /// Creates an iterator over the keys of the specified entries.
+  pub(crate) fn new(entries: slice::Iter<'a, Entry<String>>) -> Self {
+    Self { entries }
+  }
+}
+
+impl<'a> Iterator for OrderedKeys<'a> {
+  type Item = &'a String;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.entries.next().map(|entry| &entry.key)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl DoubleEndedIterator for OrderedKeys<'_> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.entries.next_back().map(|entry| &entry.key)
+  }
+}
+
+impl ExactSizeIterator for OrderedKeys<'_> {}
+
+impl FusedIterator for OrderedKeys<'_> {}
+
+/// Iterator over the values of [KeyValuePairs](crate::KeyValuePairs), in document order.
+///
+/// Returned by [KeyValuePairs::ordered_values](crate::KeyValuePairs::ordered_values).
+#[derive(Debug, Clone)]
+pub struct OrderedValues<'a> {
+  entries: slice::Iter<'a, Entry<String>>,
+}
+
+impl<'a> OrderedValues<'a> {
+  /// Creates an iterator over the values of the specified entries.
+  pub(crate) fn new(entries: slice::Iter<'a, Entry<String>>) -> Self {
+    Self { entries }
+  }
+}
+
+impl<'a> Iterator for OrderedValues<'a> {
+  type Item = &'a String;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.entries.next().map(|entry| &entry.value)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl DoubleEndedIterator for OrderedValues<'_> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.entries.next_back().map(|entry| &entry.value)
+  }
+}
+
+impl ExactSizeIterator for OrderedValues<'_> {}
+
+impl FusedIterator for OrderedValues<'_> {}
+
+/// Owning iterator over the keys with associated values of [KeyValuePairs](crate::KeyValuePairs),
+/// each key is returned once, in document order.
+#[derive(Clone)]
+pub struct IntoIter {
+  entries: vec::IntoIter<Entry<String>>,
+}
+
+impl IntoIter {
+  /// Creates an iterator over the keys and values of the specified entries.
+  pub(crate) fn new(entries: Vec<Entry<String>>) -> Self {
+    Self { entries: entries.into_iter() }
+  }
+}
+
+impl Iterator for IntoIter {
+  type Item = (String, String);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.entries.next().map(|entry| (entry.key, entry.value))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.entries.size_hint()
+  }
+}
+
+impl DoubleEndedIterator for IntoIter {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.entries.next_back().map(|entry| (entry.key, entry.value))
+  }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl FusedIterator for IntoIter {}
+
+impl Debug for IntoIter {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_list().entries(self.entries.as_slice().iter().map(|entry| (&entry.key, &entry.value))).finish()
+  }
+}
@@ -47,8 +47,36 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(rustdoc::missing_crate_level_docs)]
 
+mod borrowed;
+mod dedent;
+mod delimiter;
+mod diagnostics;
+mod document;
+mod errors;
+mod iter;
+mod line_ending;
 mod loader;
 mod model;
+mod options;
+mod parser;
+mod scanner;
+mod span;
+mod table;
+mod writer;
 
-pub use loader::{load_from_file, load_from_file_markers, load_from_string, load_from_string_markers};
-pub use model::KeyValuePairs;
+pub use borrowed::KeyValuePairsRef;
+pub use dedent::Dedent;
+pub use delimiter::Delimiter;
+pub use diagnostics::{Diagnostic, Severity};
+pub use document::Document;
+pub use errors::{Error, ErrorKind, Result};
+pub use iter::{IntoIter, Keys, OrderedKeys, OrderedValues, Values};
+pub use line_ending::LineEnding;
+pub use loader::{
+  load_from_file, load_from_file_markers, load_from_file_with_diagnostics, load_from_reader, load_from_string, load_from_string_markers, load_from_string_ref,
+  load_from_string_with_diagnostics, try_load_from_file, try_load_from_file_markers, try_load_from_string, try_load_from_string_markers,
+};
+pub use model::{DuplicatePolicy, KeyValuePairs, TrimPolicy};
+pub use options::{LoaderOptions, WriterOptions};
+pub use parser::{Event, Parser};
+pub use span::{Position, Span};
@@ -0,0 +1,48 @@
+//! # Line endings of the KIVI source text
+
+use crate::scanner::{CR, LF};
+
+/// Style of line endings.
+///
+/// All three line endings are recognized as line breaks, regardless of the style used in the source text.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::LineEnding;
+///
+/// assert_eq!(Some(LineEnding::CrLf), LineEnding::detect("a\r\nb\nc"));
+/// assert_eq!(None, LineEnding::detect("a"));
+/// assert_eq!("\r\n", LineEnding::CrLf.as_str());
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+  /// Line feed (`\n`), used on Unix-like systems.
+  #[default]
+  Lf,
+  /// Carriage return followed by line feed (`\r\n`), used on Windows.
+  CrLf,
+  /// Carriage return (`\r`), used on classic Mac OS.
+  Cr,
+}
+
+impl LineEnding {
+  /// Returns the style of the first line ending in the specified text, or [None] when there is no line ending.
+  pub fn detect(text: &str) -> Option<Self> {
+    let index = text.find([LF, CR])?;
+    Some(match &text[index..] {
+      rest if rest.starts_with("\r\n") => Self::CrLf,
+      rest if rest.starts_with(CR) => Self::Cr,
+      _ => Self::Lf,
+    })
+  }
+
+  /// Returns the characters of the line ending.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Lf => "\n",
+      Self::CrLf => "\r\n",
+      Self::Cr => "\r",
+    }
+  }
+}
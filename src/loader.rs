@@ -1,9 +1,17 @@
 //! # Implementation of KIVI deserialization functions
 
-use crate::model::KeyValuePairs;
-use normalized_line_endings::{Normalized, LF};
+use crate::borrowed::KeyValuePairsRef;
+use crate::diagnostics::Diagnostic;
+use crate::errors::{ErrorKind, Result};
+use crate::model::{DuplicatePolicy, KeyValuePairs};
+use crate::options::LoaderOptions;
+use crate::parser::{Event, Parser};
+use crate::span::Span;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
-use std::{fs, io};
 
 /// Loads key-value pairs from string in KIVI format using
 /// quotation mark (U+0022) as a default multiline marker.
@@ -65,7 +73,7 @@ use std::{fs, io};
 /// assert_eq!("b1\n    b2", kvp.get("a1\n    a2").unwrap());
 /// ```
 pub fn load_from_string(input: &str) -> KeyValuePairs {
-  Loader::new(input, &['"']).load()
+  LoaderOptions::shared().load_str_with_diagnostics(input).0
 }
 
 /// Loads key-value pairs from string in KIVI format using
@@ -130,7 +138,27 @@ pub fn load_from_string(input: &str) -> KeyValuePairs {
 /// assert_eq!("b1\n    b2", kvp.get("a1\n    a2").unwrap());
 /// ```
 pub fn load_from_string_markers(input: &str, markers: &[char]) -> KeyValuePairs {
-  Loader::new(input, markers).load()
+  LoaderOptions::new().markers(markers).load_str_with_diagnostics(input).0
+}
+
+/// Loads key-value pairs from string in KIVI format using
+/// quotation mark (U+0022) as a default multiline marker,
+/// borrowing keys and values from the input.
+///
+/// Keys and values are allocated only when they differ from the source text,
+/// see [KeyValuePairsRef] for details.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::load_from_string_ref;
+///
+/// let kvp = load_from_string_ref("a\nb\n\"c\nd\"\ne\n");
+/// assert_eq!("b", kvp.get("a").unwrap());
+/// assert_eq!("e", kvp.get("c\nd").unwrap());
+/// ```
+pub fn load_from_string_ref(input: &str) -> KeyValuePairsRef<'_> {
+  Loader::new(LoaderOptions::shared(), KeyValuePairsRef::new()).load(Parser::new(input)).0
 }
 
 /// Loads key-value pairs from file in KIVI format.
@@ -155,7 +183,7 @@ pub fn load_from_string_markers(input: &str, markers: &[char]) -> KeyValuePairs
 /// }
 /// ```
 pub fn load_from_file<P: AsRef<Path>>(path: P) -> io::Result<KeyValuePairs> {
-  Ok(load_from_string(&fs::read_to_string(path)?))
+  load_from_file_markers(path, &['"'])
 }
 
 /// Loads key-value pairs from file in KIVI format using
@@ -175,136 +203,250 @@ pub fn load_from_file<P: AsRef<Path>>(path: P) -> io::Result<KeyValuePairs> {
 /// }
 /// ```
 pub fn load_from_file_markers<P: AsRef<Path>>(path: P, markers: &[char]) -> io::Result<KeyValuePairs> {
-  Ok(load_from_string_markers(&fs::read_to_string(path)?, markers))
+  Ok(load_reader(BufReader::new(File::open(path)?), &LoaderOptions::new().markers(markers))?.0)
 }
 
-/// Empty character (zero).
-pub const NULL: char = 0 as char;
+/// Loads key-value pairs from buffered reader in KIVI format using
+/// quotation mark (U+0022) as a default multiline marker.
+///
+/// The input is read and parsed incrementally, without reading the whole input into memory.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::io::BufReader;
+/// use std::fs::File;
+/// use kivi::load_from_reader;
+///
+/// fn main() -> io::Result<()> {
+///     let kvp = load_from_reader("a\r\nb\r\n".as_bytes())?;
+///     assert_eq!("b", kvp.get("a").unwrap());
+///     let kvp = load_from_reader(BufReader::new(File::open("./tests/data/properties.kivi")?))?;
+///     assert_eq!("127.0.0.1", kvp.get("host").unwrap());
+///     Ok(())
+/// }
+/// ```
+pub fn load_from_reader<R: BufRead>(reader: R) -> io::Result<KeyValuePairs> {
+  Ok(load_reader(reader, LoaderOptions::shared())?.0)
+}
 
-/// Loader states.
-#[derive(Copy, Clone)]
-enum State {
-  Key,
-  KeyExt,
-  Value,
-  ValueExt,
+/// Loads key-value pairs from string in KIVI format using
+/// quotation mark (U+0022) as a default multiline marker.
+///
+/// Unlike [load_from_string], this function reports malformed input
+/// (unterminated multiline keys and values, keys without values)
+/// as an [Error](crate::Error) instead of silently skipping it.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{try_load_from_string, ErrorKind};
+///
+/// let kvp = try_load_from_string("a\nb\n").unwrap();
+/// assert_eq!("b", kvp.get("a").unwrap());
+///
+/// let err = try_load_from_string("a\n\"b\n").unwrap_err();
+/// assert_eq!(&ErrorKind::UnterminatedValue, err.kind());
+/// assert_eq!(2, err.line());
+/// assert_eq!(1, err.column());
+/// ```
+pub fn try_load_from_string(input: &str) -> Result<KeyValuePairs> {
+  LoaderOptions::shared_strict().load_str(input)
 }
 
-struct Loader<'a> {
-  state: State,
-  buffer: String,
-  key: String,
-  marker: char,
-  markers: &'a [char],
-  input: &'a str,
-  output: KeyValuePairs,
+/// Loads key-value pairs from string in KIVI format using
+/// custom multiline markers, reporting malformed input as an [Error](crate::Error).
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{try_load_from_string_markers, ErrorKind};
+///
+/// let kvp = try_load_from_string_markers("@a@\nb\n", &['@']).unwrap();
+/// assert_eq!("b", kvp.get("a").unwrap());
+///
+/// let err = try_load_from_string_markers("@a\nb\n", &['@']).unwrap_err();
+/// assert_eq!(&ErrorKind::UnterminatedKey, err.kind());
+/// assert_eq!(1, err.line());
+/// assert_eq!(1, err.column());
+/// ```
+pub fn try_load_from_string_markers(input: &str, markers: &[char]) -> Result<KeyValuePairs> {
+  LoaderOptions::new().markers(markers).strict(true).load_str(input)
 }
 
-impl<'a> Loader<'a> {
-  /// Created a loader with default settings.
-  fn new(input: &'a str, markers: &'a [char]) -> Self {
-    Loader {
-      state: State::Key,
-      buffer: String::new(),
-      key: String::new(),
-      marker: 0 as char,
-      markers,
-      input,
-      output: KeyValuePairs::new(),
-    }
-  }
+/// Loads key-value pairs from file in KIVI format,
+/// reporting malformed input as an [Error](crate::Error).
+///
+/// The default multiline key or value marker is a quotation mark (U+0022).
+///
+/// # Examples
+///
+/// ```
+/// use kivi::try_load_from_file;
+///
+/// fn main() -> kivi::Result<()> {
+///     let kvp = try_load_from_file("./tests/data/properties.kivi")?;
+///     assert_eq!("127.0.0.1", kvp.get("host").unwrap());
+///     Ok(())
+/// }
+/// ```
+pub fn try_load_from_file<P: AsRef<Path>>(path: P) -> Result<KeyValuePairs> {
+  LoaderOptions::shared_strict().load_file(path)
+}
 
-  /// Loads key-value pairs from string.
-  fn load(mut self) -> KeyValuePairs {
-    let mut chars = self.input.chars().normalized().peekable();
-    loop {
-      let current_char = chars.next().unwrap_or(NULL);
-      if current_char == NULL {
-        return self.output;
-      }
-      let next_char = chars.peek().cloned().unwrap_or(NULL);
-      match self.state {
-        State::Key => match (current_char, next_char) {
-          (ch, _) if self.is_allowed_marker(ch) => {
-            self.marker = ch;
-            self.clear_buffer(State::KeyExt);
-          }
-          (LF, _) => self.consume_non_empty_key(),
-          (ch, _) => self.consume_char(ch),
-        },
-        State::KeyExt => match (current_char, next_char) {
-          (ch, LF) if self.is_marker(ch) => self.consume_key(),
-          (ch, _) => self.consume_char(ch),
-        },
-        State::Value => match (current_char, next_char) {
-          (ch, _) if self.is_allowed_marker(ch) => {
-            self.marker = ch;
-            self.clear_buffer(State::ValueExt);
-          }
-          (LF, _) => self.consume_non_empty_value(),
-          (ch1, ch2) => {
-            self.consume_char(ch1);
-            if ch2 == NULL {
-              self.consume_non_empty_value();
-            }
-          }
-        },
-        State::ValueExt => match (current_char, next_char) {
-          (ch, LF) if self.is_marker(ch) => self.consume_value(),
-          (ch, _) => self.consume_char(ch),
-        },
-      }
-    }
-  }
+/// Loads key-value pairs from file in KIVI format using
+/// custom multiline markers, reporting malformed input as an [Error](crate::Error).
+///
+/// # Examples
+///
+/// ```
+/// use kivi::try_load_from_file_markers;
+///
+/// fn main() -> kivi::Result<()> {
+///     let kvp = try_load_from_file_markers("./tests/data/issues.kivi", &['@','~','^'])?;
+///     assert_eq!("Build a separate\n server", kvp.get("Issue1").unwrap());
+///     Ok(())
+/// }
+/// ```
+pub fn try_load_from_file_markers<P: AsRef<Path>>(path: P, markers: &[char]) -> Result<KeyValuePairs> {
+  LoaderOptions::new().markers(markers).strict(true).load_file(path)
+}
 
-  /// Consumes the specified character.
-  fn consume_char(&mut self, ch: char) {
-    self.buffer.push(ch);
-  }
+/// Loads key-value pairs from string in KIVI format using
+/// custom multiline markers, collecting diagnostics for malformed input.
+///
+/// The returned key-value pairs are the same as returned by [load_from_string_markers],
+/// malformed input is skipped and reported in the returned diagnostics.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{load_from_string_with_diagnostics, ErrorKind, Severity};
+///
+/// let (kvp, diagnostics) = load_from_string_with_diagnostics("a\nb\na\nc\nd\n", &['"']);
+/// assert_eq!("c", kvp.get("a").unwrap());
+/// assert_eq!(2, diagnostics.len());
+/// assert_eq!(Severity::Warning, diagnostics[0].severity());
+/// assert_eq!("duplicate-key", diagnostics[0].code());
+/// assert_eq!(3, diagnostics[0].span().start().line());
+/// assert_eq!(Severity::Error, diagnostics[1].severity());
+/// assert_eq!(&ErrorKind::MissingValue, diagnostics[1].kind());
+/// assert_eq!(5, diagnostics[1].span().start().line());
+/// ```
+pub fn load_from_string_with_diagnostics(input: &str, markers: &[char]) -> (KeyValuePairs, Vec<Diagnostic>) {
+  LoaderOptions::new().markers(markers).load_str_with_diagnostics(input)
+}
 
-  fn consume_key(&mut self) {
-    self.key.clear();
-    self.key.push_str(&self.buffer);
-    self.buffer.clear();
-    self.state = State::Value;
-  }
+/// Loads key-value pairs from file in KIVI format using
+/// custom multiline markers, collecting diagnostics for malformed input.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use kivi::load_from_file_with_diagnostics;
+///
+/// fn main() -> io::Result<()> {
+///     let (kvp, diagnostics) = load_from_file_with_diagnostics("./tests/data/issues.kivi", &['@','~','^'])?;
+///     assert_eq!("Build a separate\n server", kvp.get("Issue1").unwrap());
+///     assert!(diagnostics.is_empty());
+///     Ok(())
+/// }
+/// ```
+pub fn load_from_file_with_diagnostics<P: AsRef<Path>>(path: P, markers: &[char]) -> io::Result<(KeyValuePairs, Vec<Diagnostic>)> {
+  load_reader(BufReader::new(File::open(path)?), &LoaderOptions::new().markers(markers))
+}
 
-  fn consume_non_empty_key(&mut self) {
-    self.buffer = self.buffer.trim().to_string();
-    if !self.buffer.is_empty() {
-      self.consume_key();
-    }
+/// Loads key-value pairs from reader using the specified options, collecting all detected problems.
+pub(crate) fn load_reader<R: BufRead>(reader: R, options: &LoaderOptions) -> io::Result<(KeyValuePairs, Vec<Diagnostic>)> {
+  let mut parser = Parser::from_reader_with_options(reader, options);
+  let output = Loader::new(options, KeyValuePairs::new()).load(&mut parser);
+  if let Some(e) = parser.take_io_error() {
+    return Err(e);
   }
+  Ok(output)
+}
 
-  fn consume_non_empty_value(&mut self) {
-    self.buffer = self.buffer.trim().to_string();
-    if !self.buffer.is_empty() {
-      self.consume_value();
-    }
-  }
+/// Storage of the loaded key-value pairs.
+pub(crate) trait Output<'a> {
+  /// Returns `true` when the specified key is present.
+  fn contains_key(&self, key: &str) -> bool;
 
-  fn consume_value(&mut self) {
-    self.output.key_value_pairs.insert(self.key.clone(), self.buffer.clone());
-    self.output.ordered_keys.push(self.key.clone());
-    self.output.ordered_values.push(self.buffer.clone());
-    self.key.clear();
-    self.buffer.clear();
-    self.state = State::Key;
-  }
+  /// Returns the number of stored key-value pairs.
+  fn len(&self) -> usize;
+
+  /// Adds a key-value pair with attached comments, the duplicated key is resolved according to the specified policy.
+  fn add(&mut self, key: Cow<'a, str>, value: Cow<'a, str>, spans: (Span, Span), comments: Vec<Cow<'a, str>>, policy: DuplicatePolicy);
+}
 
-  /// Clears the input buffer and switches to the next state.
-  fn clear_buffer(&mut self, next_state: State) {
-    self.buffer.clear();
-    self.state = next_state;
+/// Loader building key-value pairs from events reported by the [Parser].
+pub(crate) struct Loader<'o, 'a, O: Output<'a>> {
+  options: &'o LoaderOptions,
+  output: O,
+  /// The last key with its span, waiting for a value.
+  key: Option<(Cow<'a, str>, Span)>,
+  /// Comments attached to the next key-value pair.
+  comments: Vec<Cow<'a, str>>,
+  /// Problems detected in the input.
+  diagnostics: Vec<Diagnostic>,
+}
+
+impl<'o, 'a, O: Output<'a>> Loader<'o, 'a, O> {
+  /// Creates a loader adding key-value pairs to the specified output, using the specified options.
+  pub(crate) fn new(options: &'o LoaderOptions, output: O) -> Self {
+    Loader {
+      options,
+      output,
+      key: None,
+      comments: vec![],
+      diagnostics: vec![],
+    }
   }
 
-  /// Returns `true` when specified character is an allowed marker.
-  fn is_allowed_marker(&self, ch: char) -> bool {
-    self.markers.contains(&ch)
+  /// Loads key-value pairs from the specified events, collecting all detected problems.
+  pub(crate) fn load(mut self, events: impl Iterator<Item = Event<'a>>) -> (O, Vec<Diagnostic>) {
+    for event in events {
+      match event {
+        Event::Key { text, span, .. } => self.key = Some((text, span)),
+        Event::Value { text, span, .. } => {
+          if !self.consume_value(text, span) {
+            break;
+          }
+        }
+        Event::Comment { text, .. } => self.comments.push(text),
+        Event::BlankLine { .. } => {
+          if self.key.is_none() {
+            self.comments.clear();
+          }
+        }
+        Event::Error { kind, span } => self.diagnostics.push(Diagnostic::error(kind, span)),
+        Event::Warning { kind, span } => self.diagnostics.push(Diagnostic::warning(kind, span)),
+      }
+    }
+    (self.output, self.diagnostics)
   }
 
-  /// Returns `true` when specified character is current marker.
-  fn is_marker(&self, ch: char) -> bool {
-    ch == self.marker
+  /// Adds the key-value pair, returns `false` when loading should be stopped.
+  fn consume_value(&mut self, value: Cow<'a, str>, value_span: Span) -> bool {
+    let Some((key, key_span)) = self.key.take() else {
+      return true;
+    };
+    let policy = self.options.duplicates;
+    let duplicate = self.output.contains_key(&key);
+    if (!duplicate || policy == DuplicatePolicy::KeepAll) && self.options.max_pairs.is_some_and(|max_pairs| self.output.len() >= max_pairs) {
+      self.diagnostics.push(Diagnostic::error(ErrorKind::TooManyPairs, key_span));
+      return false;
+    }
+    if duplicate {
+      match policy {
+        DuplicatePolicy::Reject => self.diagnostics.push(Diagnostic::error(ErrorKind::DuplicateKey, key_span)),
+        DuplicatePolicy::KeepAll => {}
+        _ => self.diagnostics.push(Diagnostic::warning(ErrorKind::DuplicateKey, key_span)),
+      }
+    }
+    let comments = std::mem::take(&mut self.comments);
+    self.output.add(key, value, (key_span, value_span), comments, policy);
+    true
   }
 }
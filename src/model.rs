@@ -1,25 +1,200 @@
 //! # Data model for key-value pairs
 
-use std::collections::hash_map::{IntoIter, Keys, Values};
-use std::collections::HashMap;
-use std::slice::Iter;
+use crate::errors::Result;
+use crate::iter::{IntoIter, Keys, OrderedKeys, OrderedValues, Values};
+use crate::loader::Output;
+use crate::options::WriterOptions;
+use crate::span::Span;
+use crate::table::Table;
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+/// Policy applied when the same key occurs more than once in a document.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+  /// The first occurrence of the key is retained, next occurrences are ignored.
+  FirstWins,
+  /// The value of the last occurrence of the key is retained,
+  /// the key keeps the position of its first occurrence.
+  #[default]
+  LastWins,
+  /// Duplicated key is an error, the first occurrence of the key is retained.
+  Reject,
+  /// All occurrences of the key are retained, lookup returns the value of the last occurrence.
+  KeepAll,
+}
+
+/// Policy for trimming whitespaces surrounding single-line keys and values.
+///
+/// Lines containing only whitespaces are always blank lines, regardless of the policy.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum TrimPolicy {
+  /// Leading and trailing whitespaces are trimmed.
+  #[default]
+  Both,
+  /// Only leading whitespaces are trimmed.
+  Start,
+  /// Only trailing whitespaces are trimmed.
+  End,
+  /// Whitespaces are preserved.
+  Preserve,
+}
+
+impl TrimPolicy {
+  /// Returns `true` when leading whitespaces are trimmed.
+  pub(crate) fn trims_start(self) -> bool {
+    matches!(self, Self::Both | Self::Start)
+  }
+
+  /// Returns `true` when trailing whitespaces are trimmed.
+  pub(crate) fn trims_end(self) -> bool {
+    matches!(self, Self::Both | Self::End)
+  }
+}
 
 /// A struct representing key-value pairs deserialized from KIVI format.
+///
+/// Key-value pairs are stored once, in document order, and indexed by keys.
+/// Comparing key-value pairs ignores the spans of keys and values in the source text.
 #[derive(Debug, Clone, PartialEq)]
 pub struct KeyValuePairs {
-  pub(crate) key_value_pairs: HashMap<String, String>,
-  pub(crate) ordered_keys: Vec<String>,
-  pub(crate) ordered_values: Vec<String>,
+  pub(crate) table: Table<String>,
 }
 
 impl KeyValuePairs {
   /// Creates an empty set of key-value pairs.
-  pub(crate) fn new() -> Self {
-    Self {
-      key_value_pairs: HashMap::new(),
-      ordered_keys: vec![],
-      ordered_values: vec![],
-    }
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::KeyValuePairs;
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// assert!(kvp.is_empty());
+  /// kvp.insert("host", "127.0.0.1");
+  /// assert_eq!("127.0.0.1", kvp.get("host").unwrap());
+  /// ```
+  pub fn new() -> Self {
+    Self { table: Table::new() }
+  }
+
+  /// Adds a key-value pair with attached comments, the duplicated key is resolved according to the specified policy.
+  pub(crate) fn add_owned(&mut self, key: String, value: String, spans: (Option<Span>, Option<Span>), comments: Vec<String>, policy: DuplicatePolicy) {
+    self.table.add(key, value, spans, comments, policy);
+  }
+
+  /// Returns `true` when the specified key is present.
+  pub(crate) fn contains_key(&self, key: &str) -> bool {
+    self.table.contains_key(key)
+  }
+
+  /// Sets the value associated with the specified key.
+  ///
+  /// When the key is present, its value is replaced and the key keeps its position,
+  /// the previous value is returned. Otherwise, the key-value pair is appended.
+  /// When the key occurs more than once, the value returned by [get](Self::get) is replaced.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let mut kvp = load_from_string("a\nb\nc\nd\n");
+  /// assert_eq!(Some("b".to_string()), kvp.insert("a", "e"));
+  /// assert_eq!(None, kvp.insert("f", "g"));
+  /// assert_eq!("a:e,c:d,f:g", kvp.ordered_key_value_pairs().map(|(k,v)| format!("{}:{}", k,v)).collect::<Vec<String>>().join(","));
+  /// ```
+  pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+    self.table.insert(key.into(), value.into())
+  }
+
+  /// Sets the value associated with the specified key, only when the key is present.
+  ///
+  /// Returns the previous value, or [None] when the key is not present.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let mut kvp = load_from_string("a\nb\n");
+  /// assert_eq!(Some("b".to_string()), kvp.set("a", "c"));
+  /// assert_eq!(None, kvp.set("d", "e"));
+  /// assert_eq!("c", kvp.get("a").unwrap());
+  /// assert_eq!(None, kvp.get("d"));
+  /// ```
+  pub fn set(&mut self, key: &str, value: impl Into<String>) -> Option<String> {
+    self.table.set(key, value.into())
+  }
+
+  /// Removes all occurrences of the specified key.
+  ///
+  /// Returns the value that was returned by [get](Self::get), or [None] when the key is not present.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let mut kvp = load_from_string("a\nb\nc\nd\n");
+  /// assert_eq!(Some("b".to_string()), kvp.remove("a"));
+  /// assert_eq!(None, kvp.remove("a"));
+  /// assert_eq!(1, kvp.len());
+  /// ```
+  pub fn remove(&mut self, key: &str) -> Option<String> {
+    self.table.remove(key)
+  }
+
+  /// Renames all occurrences of the specified key, keeping their positions and values.
+  ///
+  /// Returns `false` and leaves key-value pairs unchanged, when the key is not present
+  /// or another key with the new name is already present.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let mut kvp = load_from_string("a\nb\nc\nd\n");
+  /// assert!(kvp.rename_key("a", "e"));
+  /// assert!(!kvp.rename_key("e", "c"));
+  /// assert!(!kvp.rename_key("x", "y"));
+  /// assert_eq!("e:b,c:d", kvp.ordered_key_value_pairs().map(|(k,v)| format!("{}:{}", k,v)).collect::<Vec<String>>().join(","));
+  /// ```
+  pub fn rename_key(&mut self, key: &str, new_key: impl Into<String>) -> bool {
+    self.table.rename(key, new_key.into())
+  }
+
+  /// Retains only the key-value pairs for which the predicate returns `true`, keeping their order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let mut kvp = load_from_string("a\n1\nb\n2\nc\n3\n");
+  /// kvp.retain(|_, value| value != "2");
+  /// assert_eq!("a,c", kvp.ordered_keys().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  /// ```
+  pub fn retain<F: FnMut(&str, &str) -> bool>(&mut self, f: F) {
+    self.table.retain(f);
+  }
+
+  /// Removes all key-value pairs.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let mut kvp = load_from_string("a\nb\n");
+  /// kvp.clear();
+  /// assert!(kvp.is_empty());
+  /// assert_eq!(None, kvp.get("a"));
+  /// ```
+  pub fn clear(&mut self) {
+    self.table.clear();
   }
 
   /// Returns the value associated with the specified key.
@@ -36,7 +211,73 @@ impl KeyValuePairs {
   /// assert_eq!(None, kvp.get("a"));
   /// ```
   pub fn get(&self, key: &str) -> Option<&String> {
-    self.key_value_pairs.get(key)
+    self.table.get(key).map(|entry| &entry.value)
+  }
+
+  /// Returns the span of the specified key in the source text.
+  ///
+  /// The span of a multiline key includes markers. When the key occurs more than once,
+  /// the span of the occurrence providing the value returned by [get](Self::get) is returned.
+  /// There is no span for keys inserted or renamed after loading.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let kvp = load_from_string("host\n127.0.0.1\n\n  \"time\nout\"\n12ms\n");
+  /// let span = kvp.span_of_key("host").unwrap();
+  /// assert_eq!((0, 1, 1), (span.start().offset(), span.start().line(), span.start().column()));
+  /// assert_eq!((4, 1, 5), (span.end().offset(), span.end().line(), span.end().column()));
+  /// let span = kvp.span_of_key("time\nout").unwrap();
+  /// assert_eq!((18, 4, 3), (span.start().offset(), span.start().line(), span.start().column()));
+  /// assert_eq!((28, 5, 5), (span.end().offset(), span.end().line(), span.end().column()));
+  /// assert_eq!(None, kvp.span_of_key("port"));
+  /// ```
+  pub fn span_of_key(&self, key: &str) -> Option<Span> {
+    self.table.get(key).and_then(|entry| entry.key_span)
+  }
+
+  /// Returns the span of the value associated with the specified key in the source text.
+  ///
+  /// The span of a multiline value includes markers.
+  /// There is no span for values changed after loading.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let input = "host\n127.0.0.1\n\ntimeout\r\n  12ms  \r\n";
+  /// let kvp = load_from_string(input);
+  /// let span = kvp.span_of_value("timeout").unwrap();
+  /// assert_eq!("12ms", &input[span.range()]);
+  /// assert_eq!(5, span.start().line());
+  /// assert_eq!(3, span.start().column());
+  /// assert_eq!(None, kvp.span_of_value("port"));
+  /// ```
+  pub fn span_of_value(&self, key: &str) -> Option<Span> {
+    self.table.get(key).and_then(|entry| entry.value_span)
+  }
+
+  /// Returns the iterator over comment lines attached to the specified key.
+  ///
+  /// Comments are recognized only when enabled with [LoaderOptions::comments](crate::LoaderOptions::comments).
+  /// Comment lines directly preceding the key, or placed between the key and its value, are attached to the key.
+  /// A blank line detaches preceding comments. Keys added after loading have no comments.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let input = "# Server settings\n\n# Host name\nhost\n127.0.0.1\n\nport\n54321\n";
+  /// let kvp = LoaderOptions::new().comments(&["#"]).load_str(input).unwrap();
+  /// assert_eq!(vec!["Host name"], kvp.comments("host").collect::<Vec<&str>>());
+  /// assert_eq!(0, kvp.comments("port").count());
+  /// ```
+  pub fn comments(&self, key: &str) -> impl Iterator<Item = &str> {
+    self.table.get(key).into_iter().flat_map(|entry| entry.comments.iter().map(String::as_str))
   }
 
   /// Returns [true] when the set of key-value pairs is empty.
@@ -53,11 +294,14 @@ impl KeyValuePairs {
   /// assert_eq!(true, kvp.is_empty());
   /// ```
   pub fn is_empty(&self) -> bool {
-    self.key_value_pairs.is_empty()
+    self.table.len() == 0
   }
 
   /// Returns the number of key-value pairs.
   ///
+  /// Duplicated keys are counted as many times as they are retained
+  /// according to [DuplicatePolicy] used while loading.
+  ///
   /// # Examples
   ///
   /// ```
@@ -70,10 +314,12 @@ impl KeyValuePairs {
   /// assert_eq!(0, kvp.len());
   /// ```
   pub fn len(&self) -> usize {
-    self.key_value_pairs.len()
+    self.table.len()
   }
 
-  /// Returns the iterator over the keys.
+  /// Returns the iterator over the keys, each key is returned once.
+  ///
+  /// Keys are given in the order of occurrences providing values returned by [get](Self::get).
   ///
   /// # Examples
   ///
@@ -85,8 +331,8 @@ impl KeyValuePairs {
   ///   assert!(key == "a" || key == "c");
   /// }
   /// ```
-  pub fn keys(&self) -> Keys<'_, String, String> {
-    self.key_value_pairs.keys()
+  pub fn keys(&self) -> Keys<'_> {
+    Keys::new(self.table.primary_entries())
   }
 
   /// Returns the iterator over ordered keys.
@@ -99,11 +345,11 @@ impl KeyValuePairs {
   /// let kvp = load_from_string("d\nc\nb\na\n");
   /// assert_eq!("d,b", kvp.ordered_keys().map(|s| s.to_owned()).collect::<Vec<String>>().join(","));
   /// ```
-  pub fn ordered_keys(&self) -> Iter<'_, String> {
-    self.ordered_keys.iter()
+  pub fn ordered_keys(&self) -> OrderedKeys<'_> {
+    OrderedKeys::new(self.table.entries().iter())
   }
 
-  /// Returns the iterator over the values.
+  /// Returns the iterator over the values associated with each key.
   ///
   /// # Examples
   ///
@@ -115,8 +361,8 @@ impl KeyValuePairs {
   ///   assert!(key == "b" || key == "d");
   /// }
   /// ```
-  pub fn values(&self) -> Values<'_, String, String> {
-    self.key_value_pairs.values()
+  pub fn values(&self) -> Values<'_> {
+    Values::new(self.table.primary_entries())
   }
 
   /// Returns the iterator over ordered values.
@@ -129,8 +375,8 @@ impl KeyValuePairs {
   /// let kvp = load_from_string("d\nc\nb\na\n");
   /// assert_eq!("c,a", kvp.ordered_values().map(|s| s.to_owned()).collect::<Vec<String>>().join(","));
   /// ```
-  pub fn ordered_values(&self) -> Iter<'_, String> {
-    self.ordered_values.iter()
+  pub fn ordered_values(&self) -> OrderedValues<'_> {
+    OrderedValues::new(self.table.entries().iter())
   }
 
   /// Returns the iterator over ordered key-value pairs.
@@ -144,15 +390,187 @@ impl KeyValuePairs {
   /// assert_eq!("d:c,b:a", kvp.ordered_key_value_pairs().map(|(k,v)| format!("{}:{}", k,v)).collect::<Vec<String>>().join(","));
   /// ```
   pub fn ordered_key_value_pairs(&self) -> impl Iterator<Item = (&String, &String)> {
-    self.ordered_keys.iter().zip(self.ordered_values.iter())
+    self.table.entries().iter().map(|entry| (&entry.key, &entry.value))
+  }
+
+  /// Returns the iterator over all values associated with the specified key, in document order.
+  ///
+  /// More than one value per key is retained only when loaded
+  /// using [DuplicatePolicy::KeepAll].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{DuplicatePolicy, LoaderOptions};
+  ///
+  /// let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  /// assert_eq!("b,e", kvp.get_all("a").collect::<Vec<&str>>().join(","));
+  /// assert_eq!("d", kvp.get_all("c").collect::<Vec<&str>>().join(","));
+  /// assert_eq!(0, kvp.get_all("x").count());
+  /// ```
+  pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+    self.table.get_all(key).map(|entry| entry.value.as_str())
+  }
+
+  /// Returns the first value associated with the specified key, in document order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{DuplicatePolicy, LoaderOptions};
+  ///
+  /// let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  /// assert_eq!("b", kvp.get_first("a").unwrap());
+  /// assert_eq!("d", kvp.get_first("c").unwrap());
+  /// assert_eq!(None, kvp.get_first("x"));
+  /// ```
+  pub fn get_first(&self, key: &str) -> Option<&str> {
+    let position = self.table.positions(key).next()?;
+    Some(self.table.entries()[position].value.as_str())
+  }
+
+  /// Returns the last value associated with the specified key, in document order.
+  ///
+  /// This is the same value as returned by [get](Self::get).
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{DuplicatePolicy, LoaderOptions};
+  ///
+  /// let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  /// assert_eq!("e", kvp.get_last("a").unwrap());
+  /// assert_eq!("d", kvp.get_last("c").unwrap());
+  /// assert_eq!(None, kvp.get_last("x"));
+  /// ```
+  pub fn get_last(&self, key: &str) -> Option<&str> {
+    self.get(key).map(|value| value.as_str())
+  }
+
+  /// Returns the iterator over keys with all their associated values.
+  ///
+  /// Keys are ordered by their first occurrence in the document,
+  /// values of each key are given in document order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{DuplicatePolicy, LoaderOptions};
+  ///
+  /// let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  /// assert_eq!("a:b|e,c:d", kvp.ordered_groups().map(|(k,v)| format!("{}:{}", k, v.join("|"))).collect::<Vec<String>>().join(","));
+  /// ```
+  pub fn ordered_groups(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+    self
+      .table
+      .entries()
+      .iter()
+      .enumerate()
+      .filter(|(position, entry)| self.table.positions(&entry.key).next() == Some(*position))
+      .map(|(_, entry)| (entry.key.as_str(), self.get_all(&entry.key).collect()))
+  }
+
+  /// Returns key-value pairs formatted in KIVI format, in document order.
+  ///
+  /// Keys and values are written in a single line, unless they would not be loaded back unchanged
+  /// (like texts containing newlines, quotation marks or leading and trailing whitespaces),
+  /// such keys and values are enclosed in quotation marks (U+0022).
+  ///
+  /// Loading the returned string using [load_from_string](crate::load_from_string) gives back
+  /// the same key-value pairs, unless a key occurs more than once
+  /// (such key-value pairs are loaded back using [DuplicatePolicy::KeepAll]).
+  ///
+  /// Fails with [ErrorKind::Unrepresentable](crate::ErrorKind::Unrepresentable) when a key or a value
+  /// can not be loaded back unchanged, that is, when it contains a carriage return
+  /// or a quotation mark followed by a newline. Use [WriterOptions] to choose from more markers.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{load_from_string, KeyValuePairs};
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// kvp.insert("host", "127.0.0.1");
+  /// kvp.insert("General\n description", " indented ");
+  /// let output = kvp.to_kivi_string().unwrap();
+  /// assert_eq!("host\n127.0.0.1\n\"General\n description\"\n\" indented \"\n", output);
+  /// assert_eq!(kvp, load_from_string(&output));
+  /// ```
+  pub fn to_kivi_string(&self) -> Result<String> {
+    WriterOptions::default().format(self)
+  }
+
+  /// Writes key-value pairs in KIVI format to the specified writer, in document order.
+  ///
+  /// The output is the same as returned by [to_kivi_string](Self::to_kivi_string),
+  /// key-value pairs are written one by one, so the whole output is not kept in memory.
+  /// When a key or a value can not be represented, the key-value pairs preceding it are already written.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let kvp = load_from_string("a\nb\n\"c\nd\"\ne\n");
+  /// let mut output = vec![];
+  /// kvp.write_to(&mut output).unwrap();
+  /// assert_eq!(b"a\nb\n\"c\nd\"\ne\n", output.as_slice());
+  /// ```
+  pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+    WriterOptions::default().write(self, writer)
+  }
+
+  /// Atomically saves key-value pairs in KIVI format to the specified file.
+  ///
+  /// See [WriterOptions::save] for details.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{load_from_file, WriterOptions};
+  ///
+  /// fn main() -> kivi::Result<()> {
+  ///     let path = std::env::temp_dir().join("kivi-save-to-file.kivi");
+  ///     let mut kvp = load_from_file("./tests/data/properties.kivi")?;
+  ///     kvp.set("host", "0.0.0.0");
+  ///     kvp.save_to_file(&path, &WriterOptions::new())?;
+  ///     assert_eq!("0.0.0.0", load_from_file(&path)?.get("host").unwrap());
+  ///     # std::fs::remove_file(&path)?;
+  ///     Ok(())
+  /// }
+  /// ```
+  pub fn save_to_file<P: AsRef<Path>>(&self, path: P, options: &WriterOptions) -> Result<()> {
+    options.save(self, path)
+  }
+}
+
+impl Default for KeyValuePairs {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'a> Output<'a> for KeyValuePairs {
+  fn contains_key(&self, key: &str) -> bool {
+    self.contains_key(key)
+  }
+
+  fn len(&self) -> usize {
+    self.len()
+  }
+
+  fn add(&mut self, key: Cow<'a, str>, value: Cow<'a, str>, spans: (Span, Span), comments: Vec<Cow<'a, str>>, policy: DuplicatePolicy) {
+    let comments = comments.into_iter().map(Cow::into_owned).collect();
+    self.add_owned(key.into_owned(), value.into_owned(), (Some(spans.0), Some(spans.1)), comments, policy);
   }
 }
 
 impl IntoIterator for KeyValuePairs {
   type Item = (String, String);
-  type IntoIter = IntoIter<String, String>;
+  type IntoIter = IntoIter;
 
+  /// Returns the iterator over the keys with associated values, each key is returned once.
   fn into_iter(self) -> Self::IntoIter {
-    self.key_value_pairs.into_iter()
+    IntoIter::new(self.table.into_primary_entries())
   }
 }
@@ -0,0 +1,856 @@
+//! # Options for loading and writing KIVI documents
+
+use crate::borrowed::KeyValuePairsRef;
+use crate::dedent::Dedent;
+use crate::delimiter::Delimiter;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::document::Document;
+use crate::errors::{Error, Result};
+use crate::line_ending::LineEnding;
+use crate::loader::{load_reader, Loader};
+use crate::model::{DuplicatePolicy, KeyValuePairs, TrimPolicy};
+use crate::parser::{Event, Parser};
+use crate::writer::{format_pairs, save_pairs, write_pairs, Style};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Builder of options used for loading KIVI documents.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{DuplicatePolicy, ErrorKind, LoaderOptions};
+///
+/// let options = LoaderOptions::new()
+///   .markers(&['@', '~'])
+///   .duplicates(DuplicatePolicy::Reject)
+///   .max_pairs(10)
+///   .strict(true);
+///
+/// let kvp = options.load_str("@a\nb@\n~c~\n").unwrap();
+/// assert_eq!("c", kvp.get("a\nb").unwrap());
+///
+/// let err = options.load_str("a\nb\na\nc\n").unwrap_err();
+/// assert_eq!(&ErrorKind::DuplicateKey, err.kind());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoaderOptions {
+  pub(crate) key_delimiters: Vec<Delimiter>,
+  pub(crate) value_delimiters: Vec<Delimiter>,
+  pub(crate) comments: Vec<String>,
+  pub(crate) strict_markers: bool,
+  pub(crate) heredoc: bool,
+  pub(crate) escapes: bool,
+  pub(crate) preserve_line_endings: bool,
+  pub(crate) dedent: Dedent,
+  pub(crate) trim_first_blank_line: bool,
+  pub(crate) trim_last_blank_line: bool,
+  pub(crate) key_trim: TrimPolicy,
+  pub(crate) value_trim: TrimPolicy,
+  pub(crate) empty_value: Option<String>,
+  pub(crate) duplicates: DuplicatePolicy,
+  pub(crate) strict: bool,
+  pub(crate) max_key_length: Option<usize>,
+  pub(crate) max_value_length: Option<usize>,
+  pub(crate) max_pairs: Option<usize>,
+}
+
+impl Default for LoaderOptions {
+  fn default() -> Self {
+    Self {
+      key_delimiters: vec![Delimiter::marker('"')],
+      value_delimiters: vec![Delimiter::marker('"')],
+      comments: vec![],
+      strict_markers: false,
+      heredoc: false,
+      escapes: false,
+      preserve_line_endings: false,
+      dedent: Dedent::None,
+      trim_first_blank_line: false,
+      trim_last_blank_line: false,
+      key_trim: TrimPolicy::Both,
+      value_trim: TrimPolicy::Both,
+      empty_value: None,
+      duplicates: DuplicatePolicy::default(),
+      strict: false,
+      max_key_length: None,
+      max_value_length: None,
+      max_pairs: None,
+    }
+  }
+}
+
+impl LoaderOptions {
+  /// Creates default options.
+  ///
+  /// By default, quotation mark (U+0022) is the only multiline marker,
+  /// comments are not recognized, duplicated keys are resolved using [DuplicatePolicy::LastWins],
+  /// malformed input is skipped and there are no limits.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the default options, created once and shared by the loading functions.
+  pub(crate) fn shared() -> &'static Self {
+    static DEFAULT: OnceLock<LoaderOptions> = OnceLock::new();
+    DEFAULT.get_or_init(Self::default)
+  }
+
+  /// Returns the default options with strict mode enabled, created once and shared by the fallible loading functions.
+  pub(crate) fn shared_strict() -> &'static Self {
+    static STRICT: OnceLock<LoaderOptions> = OnceLock::new();
+    STRICT.get_or_init(|| Self::default().strict(true))
+  }
+
+  /// Sets the allowed multiline markers, for both keys and values.
+  pub fn markers(mut self, markers: &[char]) -> Self {
+    self.key_delimiters = Delimiter::from_markers(markers);
+    self.value_delimiters = Delimiter::from_markers(markers);
+    self
+  }
+
+  /// Sets the allowed markers of multiline keys.
+  ///
+  /// Characters that are not key markers can be used literally in single-line keys.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let options = LoaderOptions::new().key_markers(&['@']).value_markers(&['"', '`']);
+  /// let kvp = options.load_str("\"quoted\" key\n`multiline\nvalue`\n@multiline\nkey@\n\"value\"\n").unwrap();
+  /// assert_eq!("multiline\nvalue", kvp.get("\"quoted\" key").unwrap());
+  /// assert_eq!("value", kvp.get("multiline\nkey").unwrap());
+  /// ```
+  pub fn key_markers(mut self, markers: &[char]) -> Self {
+    self.key_delimiters = Delimiter::from_markers(markers);
+    self
+  }
+
+  /// Sets the allowed markers of multiline values.
+  ///
+  /// Characters that are not value markers can be used literally in single-line values.
+  pub fn value_markers(mut self, markers: &[char]) -> Self {
+    self.value_delimiters = Delimiter::from_markers(markers);
+    self
+  }
+
+  /// Sets the allowed pairs of opening and closing delimiters, for both keys and values.
+  ///
+  /// Delimiters may be longer than one character, like `<<<` and `>>>`.
+  /// A multiline key or value starts with the opening delimiter and ends with the closing delimiter
+  /// followed by a newline, so it may contain the opening delimiter freely.
+  /// Empty delimiters and delimiters containing line endings are skipped.
+  /// When more than one opening delimiter matches, the first one is used.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let options = LoaderOptions::new().delimiters(&[("[", "]"), ("<<<", ">>>")]);
+  /// let kvp = options.load_str("[a\nb]\n<<<[c]\n>>>\n").unwrap();
+  /// assert_eq!("[c]\n", kvp.get("a\nb").unwrap());
+  /// ```
+  pub fn delimiters(mut self, delimiters: &[(&str, &str)]) -> Self {
+    self.key_delimiters = Delimiter::from_pairs(delimiters);
+    self.value_delimiters = Delimiter::from_pairs(delimiters);
+    self
+  }
+
+  /// Sets the allowed pairs of opening and closing delimiters of multiline keys.
+  pub fn key_delimiters(mut self, delimiters: &[(&str, &str)]) -> Self {
+    self.key_delimiters = Delimiter::from_pairs(delimiters);
+    self
+  }
+
+  /// Sets the allowed pairs of opening and closing delimiters of multiline values.
+  pub fn value_delimiters(mut self, delimiters: &[(&str, &str)]) -> Self {
+    self.value_delimiters = Delimiter::from_pairs(delimiters);
+    self
+  }
+
+  /// Sets the strict markers mode.
+  ///
+  /// With strict markers, an opening marker is recognized only as the first non-whitespace
+  /// character in a line, anywhere else it is an ordinary character of a single-line key or value.
+  /// Otherwise, an opening marker anywhere in the line starts a multiline key or value,
+  /// discarding the text preceding the marker.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let input = "message\nHe said \"hello\"\n  \"multiline\nmessage\"\nsaid \"hi\"\n";
+  /// let kvp = LoaderOptions::new().strict_markers(true).load_str(input).unwrap();
+  /// assert_eq!("He said \"hello\"", kvp.get("message").unwrap());
+  /// assert_eq!("said \"hi\"", kvp.get("multiline\nmessage").unwrap());
+  /// ```
+  pub fn strict_markers(mut self, strict_markers: bool) -> Self {
+    self.strict_markers = strict_markers;
+    self
+  }
+
+  /// Enables heredoc multiline keys and values.
+  ///
+  /// A line consisting of `<<` followed by the name of the terminator, like `<<END`, opens
+  /// a multiline key or value, that ends at the first line consisting exactly of the terminator.
+  /// Terminator names consist of alphanumeric characters, `_` and `-`.
+  /// The content may contain any text, so there is no need to choose a marker absent from the content.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let input = "query\n<<SQL\nSELECT \"name\"\n  FROM users\n SQL\nSQL\n";
+  /// let kvp = LoaderOptions::new().heredoc(true).load_str(input).unwrap();
+  /// assert_eq!("SELECT \"name\"\n  FROM users\n SQL", kvp.get("query").unwrap());
+  /// ```
+  pub fn heredoc(mut self, heredoc: bool) -> Self {
+    self.heredoc = heredoc;
+    self
+  }
+
+  /// Enables escape sequences in multiline keys and values enclosed in delimiters.
+  ///
+  /// Recognized escape sequences are `\n` (newline), `\t` (tab), `\\` (backslash), `\"` (quotation mark),
+  /// `\u{...}` (Unicode code point with up to six hexadecimal digits) and a backslash followed by any character
+  /// of the delimiters. Escaped characters never close a multiline key or value.
+  /// Unknown or malformed escape sequences are reported as [ErrorKind::InvalidEscape](crate::ErrorKind::InvalidEscape)
+  /// and kept verbatim. Single-line keys and values and heredocs are always raw, which is also the default for all keys and values.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{ErrorKind, LoaderOptions};
+  ///
+  /// let options = LoaderOptions::new().escapes(true).strict(true);
+  /// let kvp = options.load_str("a\\b\n\"say \\\"\nc:\\td\\u{263A}\"\n").unwrap();
+  /// assert_eq!("say \"\nc:\td\u{263A}", kvp.get("a\\b").unwrap());
+  ///
+  /// let err = options.load_str("a\n\"b\n c\\d\"\n").unwrap_err();
+  /// assert_eq!(&ErrorKind::InvalidEscape, err.kind());
+  /// assert_eq!((3, 3), (err.line(), err.column()));
+  /// ```
+  pub fn escapes(mut self, escapes: bool) -> Self {
+    self.escapes = escapes;
+    self
+  }
+
+  /// Sets whether original line endings are kept in multiline keys and values.
+  ///
+  /// By default, line endings in multiline keys and values are normalized to LF.
+  /// Either way, LF, CR LF and CR are all recognized as line breaks.
+  /// The style of line endings used in the input is reported by [Parser::line_ending]
+  /// and [Document::line_ending].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let input = "script\r\n\"@echo off\r\necho done\r\n\"\r\n";
+  /// let kvp = LoaderOptions::new().preserve_line_endings(true).load_str(input).unwrap();
+  /// assert_eq!("@echo off\r\necho done\r\n", kvp.get("script").unwrap());
+  /// let kvp = LoaderOptions::new().load_str(input).unwrap();
+  /// assert_eq!("@echo off\necho done\n", kvp.get("script").unwrap());
+  /// ```
+  pub fn preserve_line_endings(mut self, preserve: bool) -> Self {
+    self.preserve_line_endings = preserve;
+    self
+  }
+
+  /// Sets the policy for stripping the indentation of multiline keys and values.
+  ///
+  /// By default, multiline keys and values keep the indentation verbatim.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{Dedent, LoaderOptions};
+  ///
+  /// let input = "  template\n  \"\n    Hello,\n      welcome!\n  \"\n";
+  /// let options = LoaderOptions::new().dedent(Dedent::Common).trim_first_blank_line(true).trim_last_blank_line(true);
+  /// assert_eq!("Hello,\n  welcome!", options.load_str(input).unwrap().get("template").unwrap());
+  ///
+  /// let input = "description\n\"First line\n second line\n  indented\"\n";
+  /// let options = LoaderOptions::new().dedent(Dedent::Marker);
+  /// assert_eq!("First line\nsecond line\n indented", options.load_str(input).unwrap().get("description").unwrap());
+  /// ```
+  pub fn dedent(mut self, dedent: Dedent) -> Self {
+    self.dedent = dedent;
+    self
+  }
+
+  /// Sets whether the first line of multiline keys and values is removed when it contains only whitespaces.
+  ///
+  /// Allows placing the opening delimiter in a separate line.
+  pub fn trim_first_blank_line(mut self, trim: bool) -> Self {
+    self.trim_first_blank_line = trim;
+    self
+  }
+
+  /// Sets whether the last line of multiline keys and values is removed, together with the preceding line ending,
+  /// when it contains only whitespaces.
+  ///
+  /// Allows placing the closing delimiter in a separate line.
+  pub fn trim_last_blank_line(mut self, trim: bool) -> Self {
+    self.trim_last_blank_line = trim;
+    self
+  }
+
+  /// Sets the policy for trimming whitespaces surrounding single-line keys and values.
+  ///
+  /// By default, single-line keys and values are trimmed using [TrimPolicy::Both].
+  /// Spans of keys and values cover the preserved whitespaces.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{LoaderOptions, TrimPolicy};
+  ///
+  /// let options = LoaderOptions::new().trim(TrimPolicy::Start);
+  /// let kvp = options.load_str("prompt\n  > \n").unwrap();
+  /// assert_eq!("> ", kvp.get("prompt").unwrap());
+  /// ```
+  pub fn trim(mut self, policy: TrimPolicy) -> Self {
+    self.key_trim = policy;
+    self.value_trim = policy;
+    self
+  }
+
+  /// Sets the policy for trimming whitespaces surrounding single-line keys.
+  pub fn key_trim(mut self, policy: TrimPolicy) -> Self {
+    self.key_trim = policy;
+    self
+  }
+
+  /// Sets the policy for trimming whitespaces surrounding single-line values.
+  pub fn value_trim(mut self, policy: TrimPolicy) -> Self {
+    self.value_trim = policy;
+    self
+  }
+
+  /// Sets the token representing an intentionally empty value.
+  ///
+  /// A single-line value consisting of the token, surrounded by optional whitespaces,
+  /// is loaded as an empty value. Empty token disables the feature, which is the default.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let options = LoaderOptions::new().empty_value("<empty>");
+  /// let kvp = options.load_str("suffix\n  <empty>\nprefix\n<empty> value\n").unwrap();
+  /// assert_eq!("", kvp.get("suffix").unwrap());
+  /// assert_eq!("<empty> value", kvp.get("prefix").unwrap());
+  /// ```
+  pub fn empty_value(mut self, token: &str) -> Self {
+    self.empty_value = (!token.is_empty()).then(|| token.to_string());
+    self
+  }
+
+  /// Sets the prefixes of comment lines, like `#` or `//`.
+  ///
+  /// A line is a comment when it starts with one of the prefixes, ignoring leading whitespaces.
+  /// Comments are recognized only outside multiline keys and values, lines inside multiline
+  /// keys and values are always their content. Comment lines directly preceding a key
+  /// are attached to that key and available using [KeyValuePairs::comments].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let options = LoaderOptions::new().comments(&["#", "//"]);
+  /// let kvp = options.load_str("# Host name\n// or IP address\nhost\n\"127.0.0.1\n# not a comment\"\n").unwrap();
+  /// assert_eq!("127.0.0.1\n# not a comment", kvp.get("host").unwrap());
+  /// assert_eq!("Host name,or IP address", kvp.comments("host").collect::<Vec<&str>>().join(","));
+  /// ```
+  pub fn comments(mut self, prefixes: &[&str]) -> Self {
+    self.comments = prefixes.iter().filter(|prefix| !prefix.is_empty()).map(|prefix| prefix.to_string()).collect();
+    self
+  }
+
+  /// Sets the policy for duplicated keys.
+  pub fn duplicates(mut self, policy: DuplicatePolicy) -> Self {
+    self.duplicates = policy;
+    self
+  }
+
+  /// Sets the strict mode.
+  ///
+  /// In strict mode, loading fails on the first problem reported
+  /// as a diagnostic with [Severity::Error](crate::Severity::Error).
+  /// Otherwise, malformed input is skipped.
+  pub fn strict(mut self, strict: bool) -> Self {
+    self.strict = strict;
+    self
+  }
+
+  /// Sets the maximum length of a key in bytes.
+  ///
+  /// Whitespaces trimmed from single-line keys are not counted, but at most 1024 bytes of them
+  /// are accepted in addition to the limit. When a longer key is encountered, loading stops.
+  pub fn max_key_length(mut self, max_key_length: usize) -> Self {
+    self.max_key_length = Some(max_key_length);
+    self
+  }
+
+  /// Sets the maximum length of a value in bytes.
+  ///
+  /// Whitespaces trimmed from single-line values are not counted, but at most 1024 bytes of them
+  /// are accepted in addition to the limit. When a longer value is encountered, loading stops.
+  pub fn max_value_length(mut self, max_value_length: usize) -> Self {
+    self.max_value_length = Some(max_value_length);
+    self
+  }
+
+  /// Sets the maximum number of loaded key-value pairs.
+  ///
+  /// When more key-value pairs are encountered, loading stops.
+  pub fn max_pairs(mut self, max_pairs: usize) -> Self {
+    self.max_pairs = Some(max_pairs);
+    self
+  }
+
+  /// Creates a [Parser] over the specified string, using these options.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{Event, LoaderOptions};
+  ///
+  /// let options = LoaderOptions::new().markers(&['@']);
+  /// let events = options.parse_str("@a@\nb\n").collect::<Vec<Event>>();
+  /// assert_eq!(2, events.len());
+  /// assert!(matches!(&events[0], Event::Key { text, delimiter: Some(delimiter), .. } if text == "a" && delimiter.open() == "@"));
+  /// assert!(matches!(&events[1], Event::Value { text, delimiter: None, .. } if text == "b"));
+  /// ```
+  pub fn parse_str<'a>(&'a self, input: &'a str) -> Parser<'a> {
+    Parser::with_options(input, self)
+  }
+
+  /// Creates a [Parser] over the specified buffered reader, using these options.
+  pub fn parse_reader<'a, R: BufRead>(&'a self, reader: R) -> Parser<'a, R> {
+    Parser::from_reader_with_options(reader, self)
+  }
+
+  /// Loads key-value pairs from string.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let kvp = LoaderOptions::new().load_str("a\nb\n").unwrap();
+  /// assert_eq!("b", kvp.get("a").unwrap());
+  /// ```
+  pub fn load_str(&self, input: &str) -> Result<KeyValuePairs> {
+    self.check(self.load_str_with_diagnostics(input))
+  }
+
+  /// Loads key-value pairs from string, collecting diagnostics for malformed input.
+  ///
+  /// Malformed input is skipped, regardless of the strict mode.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let (kvp, diagnostics) = LoaderOptions::new().max_value_length(3).load_str_with_diagnostics("a\nb\nc\ndefg\n");
+  /// assert_eq!(1, kvp.len());
+  /// assert_eq!("value-too-long", diagnostics[0].code());
+  /// ```
+  pub fn load_str_with_diagnostics(&self, input: &str) -> (KeyValuePairs, Vec<Diagnostic>) {
+    Loader::new(self, KeyValuePairs::new()).load(self.parse_str(input))
+  }
+
+  /// Loads key-value pairs from string, borrowing keys and values from the input.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let options = LoaderOptions::new().markers(&['@']);
+  /// let kvp = options.load_str_ref("@a\nb@\nc\n").unwrap();
+  /// assert_eq!("c", kvp.get("a\nb").unwrap());
+  /// ```
+  pub fn load_str_ref<'a>(&'a self, input: &'a str) -> Result<KeyValuePairsRef<'a>> {
+    self.check(self.load_str_ref_with_diagnostics(input))
+  }
+
+  /// Loads key-value pairs from string, borrowing keys and values from the input
+  /// and collecting diagnostics for malformed input.
+  ///
+  /// Malformed input is skipped, regardless of the strict mode.
+  pub fn load_str_ref_with_diagnostics<'a>(&'a self, input: &'a str) -> (KeyValuePairsRef<'a>, Vec<Diagnostic>) {
+    Loader::new(self, KeyValuePairsRef::new()).load(self.parse_str(input))
+  }
+
+  /// Loads a lossless [Document] from string, for editing that preserves formatting.
+  ///
+  /// In strict mode fails on the first error, otherwise malformed input is retained in the document.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let options = LoaderOptions::new().markers(&['@']);
+  /// let mut document = options.load_document("@a\nb@\n  c\n").unwrap();
+  /// document.set("a\nb", "d\ne").unwrap();
+  /// assert_eq!("@a\nb@\n  @d\ne@\n", document.to_string());
+  /// ```
+  pub fn load_document(&self, input: &str) -> Result<Document> {
+    self.check(self.load_document_with_diagnostics(input))
+  }
+
+  /// Loads a lossless [Document] from string, collecting diagnostics for malformed input.
+  ///
+  /// Malformed input is retained in the document, regardless of the strict mode.
+  pub fn load_document_with_diagnostics(&self, input: &str) -> (Document, Vec<Diagnostic>) {
+    let events = self.parse_str(input).collect::<Vec<Event>>();
+    let (_, diagnostics) = Loader::new(self, KeyValuePairsRef::new()).load(events.iter().cloned());
+    (Document::from_events(input, events.into_iter(), self), diagnostics)
+  }
+
+  /// Loads key-value pairs from file.
+  ///
+  /// The file is read and parsed incrementally, see [load_reader](Self::load_reader).
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// fn main() -> kivi::Result<()> {
+  ///     let kvp = LoaderOptions::new().markers(&['@', '~', '^']).load_file("./tests/data/issues.kivi")?;
+  ///     assert_eq!("Build a separate\n server", kvp.get("Issue1").unwrap());
+  ///     Ok(())
+  /// }
+  /// ```
+  pub fn load_file<P: AsRef<Path>>(&self, path: P) -> Result<KeyValuePairs> {
+    self.load_reader(BufReader::new(File::open(path)?))
+  }
+
+  /// Loads key-value pairs from file, collecting diagnostics for malformed input.
+  pub fn load_file_with_diagnostics<P: AsRef<Path>>(&self, path: P) -> Result<(KeyValuePairs, Vec<Diagnostic>)> {
+    self.load_reader_with_diagnostics(BufReader::new(File::open(path)?))
+  }
+
+  /// Loads key-value pairs from buffered reader.
+  ///
+  /// The input is read and parsed incrementally, so only the loaded key-value pairs
+  /// are kept in memory. Use [max_key_length](Self::max_key_length) and [max_value_length](Self::max_value_length)
+  /// to bound the memory used while reading a single key or value, including trimmed whitespaces.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::LoaderOptions;
+  ///
+  /// let kvp = LoaderOptions::new().load_reader("a\nb\n".as_bytes()).unwrap();
+  /// assert_eq!("b", kvp.get("a").unwrap());
+  /// ```
+  pub fn load_reader<R: BufRead>(&self, reader: R) -> Result<KeyValuePairs> {
+    self.check(load_reader(reader, self)?)
+  }
+
+  /// Loads key-value pairs from buffered reader, collecting diagnostics for malformed input.
+  ///
+  /// Malformed input is skipped, regardless of the strict mode.
+  pub fn load_reader_with_diagnostics<R: BufRead>(&self, reader: R) -> Result<(KeyValuePairs, Vec<Diagnostic>)> {
+    Ok(load_reader(reader, self)?)
+  }
+
+  /// Returns loaded key-value pairs, in strict mode fails on the first reported error.
+  fn check<T>(&self, (output, diagnostics): (T, Vec<Diagnostic>)) -> Result<T> {
+    if self.strict {
+      if let Some(diagnostic) = diagnostics.into_iter().find(|diagnostic| diagnostic.severity() == Severity::Error) {
+        return Err(Error::new(diagnostic.kind().clone(), diagnostic.span().start()));
+      }
+    }
+    Ok(output)
+  }
+}
+
+/// Builder of options used for writing KIVI documents.
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{load_from_string_markers, KeyValuePairs, WriterOptions};
+///
+/// let mut kvp = KeyValuePairs::new();
+/// kvp.insert("quote", "He said \"hello\"\nand left");
+/// kvp.insert("code", "let s = \"a\";\nlet t = \"b\"");
+///
+/// let options = WriterOptions::new().markers(&['"', '~']);
+/// let output = options.format(&kvp).unwrap();
+/// assert_eq!("quote\n~He said \"hello\"\nand left~\ncode\n\"let s = \"a\";\nlet t = \"b\"\"\n", output);
+/// assert_eq!(kvp, load_from_string_markers(&output, &['"', '~']));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriterOptions {
+  pub(crate) key_delimiters: Vec<Delimiter>,
+  pub(crate) value_delimiters: Vec<Delimiter>,
+  pub(crate) escapes: bool,
+  pub(crate) line_ending: LineEnding,
+  pub(crate) final_newline: bool,
+  pub(crate) bom: bool,
+  pub(crate) blank_lines: usize,
+  pub(crate) backup: bool,
+}
+
+impl Default for WriterOptions {
+  fn default() -> Self {
+    Self {
+      key_delimiters: vec![Delimiter::marker('"')],
+      value_delimiters: vec![Delimiter::marker('"')],
+      escapes: false,
+      line_ending: LineEnding::Lf,
+      final_newline: true,
+      bom: false,
+      blank_lines: 0,
+      backup: false,
+    }
+  }
+}
+
+impl WriterOptions {
+  /// Creates default options.
+  ///
+  /// By default, quotation mark (U+0022) is the only multiline marker, lines end with LF,
+  /// the output ends with a line ending and no backup is kept when saving files.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the candidate multiline markers, in order of preference, for both keys and values.
+  ///
+  /// Each key and value that can not be written in a single line is enclosed
+  /// in the first candidate marker that keeps it unchanged when loaded back.
+  /// The written document should be loaded using the same set of markers,
+  /// because none of the candidates is allowed in single-line keys and values.
+  pub fn markers(mut self, markers: &[char]) -> Self {
+    self.key_delimiters = Delimiter::from_markers(markers);
+    self.value_delimiters = Delimiter::from_markers(markers);
+    self
+  }
+
+  /// Sets the candidate markers of multiline keys, in order of preference.
+  ///
+  /// The written document should be loaded using the same key markers,
+  /// see [LoaderOptions::key_markers].
+  pub fn key_markers(mut self, markers: &[char]) -> Self {
+    self.key_delimiters = Delimiter::from_markers(markers);
+    self
+  }
+
+  /// Sets the candidate markers of multiline values, in order of preference.
+  ///
+  /// The written document should be loaded using the same value markers,
+  /// see [LoaderOptions::value_markers].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{KeyValuePairs, LoaderOptions, WriterOptions};
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// kvp.insert("\"quoted\" key", "multiline\nvalue");
+  ///
+  /// let output = WriterOptions::new().key_markers(&['@']).value_markers(&['`']).format(&kvp).unwrap();
+  /// assert_eq!("\"quoted\" key\n`multiline\nvalue`\n", output);
+  /// assert_eq!(kvp, LoaderOptions::new().key_markers(&['@']).value_markers(&['`']).load_str(&output).unwrap());
+  /// ```
+  pub fn value_markers(mut self, markers: &[char]) -> Self {
+    self.value_delimiters = Delimiter::from_markers(markers);
+    self
+  }
+
+  /// Sets the candidate pairs of opening and closing delimiters, in order of preference, for both keys and values.
+  ///
+  /// The written document should be loaded using the same delimiters, see [LoaderOptions::delimiters].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{KeyValuePairs, WriterOptions};
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// kvp.insert("a", "b\nc]\n");
+  ///
+  /// let options = WriterOptions::new().delimiters(&[("[", "]"), ("<<<", ">>>")]);
+  /// assert_eq!("a\n<<<b\nc]\n>>>\n", options.format(&kvp).unwrap());
+  /// ```
+  pub fn delimiters(mut self, delimiters: &[(&str, &str)]) -> Self {
+    self.key_delimiters = Delimiter::from_pairs(delimiters);
+    self.value_delimiters = Delimiter::from_pairs(delimiters);
+    self
+  }
+
+  /// Sets the candidate pairs of opening and closing delimiters of multiline keys, in order of preference.
+  pub fn key_delimiters(mut self, delimiters: &[(&str, &str)]) -> Self {
+    self.key_delimiters = Delimiter::from_pairs(delimiters);
+    self
+  }
+
+  /// Sets the candidate pairs of opening and closing delimiters of multiline values, in order of preference.
+  pub fn value_delimiters(mut self, delimiters: &[(&str, &str)]) -> Self {
+    self.value_delimiters = Delimiter::from_pairs(delimiters);
+    self
+  }
+
+  /// Sets whether texts enclosed in delimiters are escaped.
+  ///
+  /// With escapes, any key or value without carriage returns can be enclosed in the first candidate delimiter.
+  /// The written document should be loaded with escapes enabled, see [LoaderOptions::escapes].
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{KeyValuePairs, LoaderOptions, WriterOptions};
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// kvp.insert("a", "say \"\n\\d");
+  ///
+  /// let output = WriterOptions::new().escapes(true).format(&kvp).unwrap();
+  /// assert_eq!("a\n\"say \\\"\n\\\\d\"\n", output);
+  /// assert_eq!(kvp, LoaderOptions::new().escapes(true).load_str(&output).unwrap());
+  /// ```
+  pub fn escapes(mut self, escapes: bool) -> Self {
+    self.escapes = escapes;
+    self
+  }
+
+  /// Sets the line ending written after keys and values and inside multiline keys and values.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{KeyValuePairs, LineEnding, WriterOptions};
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// kvp.insert("a", "b\nc");
+  /// kvp.insert("d", "e");
+  ///
+  /// let options = WriterOptions::new().line_ending(LineEnding::CrLf).bom(true).blank_lines(1);
+  /// assert_eq!("\u{FEFF}a\r\n\"b\r\nc\"\r\n\r\nd\r\ne\r\n", options.format(&kvp).unwrap());
+  /// ```
+  pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+    self.line_ending = line_ending;
+    self
+  }
+
+  /// Sets whether the output ends with a line ending, enabled by default.
+  ///
+  /// The last value enclosed in delimiters other than heredoc is always followed by a line ending,
+  /// which is required to close it.
+  pub fn final_newline(mut self, final_newline: bool) -> Self {
+    self.final_newline = final_newline;
+    self
+  }
+
+  /// Sets whether the output starts with the UTF-8 byte order mark, disabled by default.
+  ///
+  /// The byte order mark at the beginning of the input is skipped when loading.
+  pub fn bom(mut self, bom: bool) -> Self {
+    self.bom = bom;
+    self
+  }
+
+  /// Sets the number of blank lines written between key-value pairs, no blank lines by default.
+  pub fn blank_lines(mut self, blank_lines: usize) -> Self {
+    self.blank_lines = blank_lines;
+    self
+  }
+
+  /// Returns the style of formatted multiline keys and values.
+  pub(crate) fn style(&self) -> Style {
+    Style {
+      line_ending: self.line_ending,
+      escapes: self.escapes,
+      preserve_line_endings: false,
+      comments: vec![],
+      heredoc: false,
+      dedent: Dedent::None,
+      trim_first_blank_line: false,
+      trim_last_blank_line: false,
+      trim: TrimPolicy::Both,
+      empty_value: None,
+    }
+  }
+
+  /// Sets whether the previous version of a saved file is kept.
+  ///
+  /// When enabled, [save](Self::save) copies the replaced file to the same path with `.bak` appended,
+  /// overwriting the previous backup.
+  pub fn backup(mut self, backup: bool) -> Self {
+    self.backup = backup;
+    self
+  }
+
+  /// Returns key-value pairs formatted in KIVI format, in document order.
+  ///
+  /// Fails with [ErrorKind::Unrepresentable](crate::ErrorKind::Unrepresentable) when none of the candidate markers
+  /// keeps a key or a value unchanged when loaded back.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{ErrorKind, KeyValuePairs, WriterOptions};
+  ///
+  /// let mut kvp = KeyValuePairs::new();
+  /// kvp.insert("a", "b\"\nc");
+  /// let err = WriterOptions::new().format(&kvp).unwrap_err();
+  /// assert_eq!(&ErrorKind::Unrepresentable, err.kind());
+  /// let output = WriterOptions::new().markers(&['"', '`']).format(&kvp).unwrap();
+  /// assert_eq!("a\n`b\"\nc`\n", output);
+  /// ```
+  pub fn format(&self, kvp: &KeyValuePairs) -> Result<String> {
+    format_pairs(kvp.ordered_key_value_pairs().map(|(key, value)| (key.as_str(), value.as_str())), self)
+  }
+
+  /// Writes key-value pairs in KIVI format to the specified writer, in document order.
+  ///
+  /// The output is the same as returned by [format](Self::format).
+  pub fn write<W: Write>(&self, kvp: &KeyValuePairs, writer: W) -> Result<()> {
+    write_pairs(writer, kvp.ordered_key_value_pairs().map(|(key, value)| (key.as_str(), value.as_str())), self)
+  }
+
+  /// Atomically saves key-value pairs in KIVI format to the specified file.
+  ///
+  /// Key-value pairs are written to a temporary file in the same directory, which is synced to disk
+  /// and then renamed to the specified path. Other processes reading the file see either the previous
+  /// or the new content, never a partially written file. When the file already exists,
+  /// its permissions are preserved. On failure, the existing file is left unchanged.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{load_from_file, KeyValuePairs, WriterOptions};
+  ///
+  /// fn main() -> kivi::Result<()> {
+  ///     let path = std::env::temp_dir().join("kivi-writer-options-save.kivi");
+  ///     let mut kvp = KeyValuePairs::new();
+  ///     kvp.insert("host", "127.0.0.1");
+  ///     WriterOptions::new().save(&kvp, &path)?;
+  ///     assert_eq!(kvp, load_from_file(&path)?);
+  ///     # std::fs::remove_file(&path)?;
+  ///     Ok(())
+  /// }
+  /// ```
+  pub fn save<P: AsRef<Path>>(&self, kvp: &KeyValuePairs, path: P) -> Result<()> {
+    save_pairs(path.as_ref(), kvp.ordered_key_value_pairs().map(|(key, value)| (key.as_str(), value.as_str())), self)
+  }
+}
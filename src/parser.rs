@@ -0,0 +1,873 @@
+//! # Pull parser for KIVI documents
+
+use crate::dedent::{reshape, Dedent};
+use crate::delimiter::Delimiter;
+use crate::errors::ErrorKind;
+use crate::line_ending::LineEnding;
+use crate::model::TrimPolicy;
+use crate::options::LoaderOptions;
+use crate::scanner::{ReaderChars, Scanner, CR, LF};
+use crate::span::{Position, Span};
+use std::borrow::Cow;
+use std::io::{self, BufRead};
+use std::mem;
+use std::ops::Range;
+use std::str::Chars;
+
+/// Event produced by the [Parser].
+///
+/// When parsing a string, the texts of keys and values are borrowed from the input,
+/// unless they differ from the source text (like multiline texts with normalized line endings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<'a> {
+  /// Key of the key-value pair.
+  Key {
+    /// Text of the key, borrowed from the input whenever possible.
+    text: Cow<'a, str>,
+    /// Span of the key in the source text, including markers.
+    span: Span,
+    /// Delimiters enclosing the multiline key, [None] for a single-line key.
+    delimiter: Option<Delimiter>,
+  },
+  /// Value of the key-value pair.
+  Value {
+    /// Text of the value, borrowed from the input whenever possible.
+    text: Cow<'a, str>,
+    /// Span of the value in the source text, including markers.
+    span: Span,
+    /// Delimiters enclosing the multiline value, [None] for a single-line value.
+    delimiter: Option<Delimiter>,
+  },
+  /// Line starting with a comment prefix, outside multiline keys and values.
+  ///
+  /// Comments are reported only when comment prefixes are enabled with [LoaderOptions::comments].
+  Comment {
+    /// Text of the comment without the prefix, trimmed.
+    text: Cow<'a, str>,
+    /// Span of the comment in the source text, including the prefix.
+    span: Span,
+  },
+  /// Line containing only whitespaces, outside multiline keys and values.
+  BlankLine {
+    /// Span of the line, without the line ending.
+    span: Span,
+  },
+  /// Problem that caused part of the input to be skipped.
+  Error {
+    /// Kind of the problem.
+    kind: ErrorKind,
+    /// Span of the source text the problem was detected in.
+    span: Span,
+  },
+  /// Suspicious input that did not cause any part of the input to be skipped.
+  Warning {
+    /// Kind of the problem.
+    kind: ErrorKind,
+    /// Span of the source text the problem was detected in.
+    span: Span,
+  },
+}
+
+/// Prefix of the line opening a heredoc, followed by the name of the terminator.
+const HEREDOC: &str = "<<";
+
+/// Number of bytes of whitespaces trimmed from a single-line key or value that may be buffered
+/// in addition to the length limit, so the limit also bounds the memory used for the whole line.
+const TRIMMED_ALLOWANCE: usize = 1024;
+
+/// Source of characters read by the parser.
+enum Source<'a, R: BufRead> {
+  Str(Chars<'a>),
+  Reader(ReaderChars<R>),
+}
+
+impl<R: BufRead> Iterator for Source<'_, R> {
+  type Item = char;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self {
+      Source::Str(chars) => chars.next(),
+      Source::Reader(chars) => chars.next(),
+    }
+  }
+}
+
+/// Parser states.
+#[derive(Copy, Clone)]
+enum State {
+  Key,
+  KeyExt,
+  Value,
+  ValueExt,
+  Comment,
+}
+
+/// Pull parser reporting keys and values of a KIVI document as a sequence of [Event]s.
+///
+/// The input is parsed lazily, each call to [next](Iterator::next)
+/// reads only as many characters as needed to produce the next event.
+/// The type parameter is the type of the buffered reader parsed by [Parser::from_reader].
+///
+/// # Examples
+///
+/// ```
+/// use kivi::{Event, Parser};
+///
+/// let mut parser = Parser::new("host\n127.0.0.1\n\n\"port\"\n54321\n");
+/// let Some(Event::Key { text, delimiter, .. }) = parser.next() else { panic!() };
+/// assert_eq!("host", text);
+/// assert_eq!(None, delimiter);
+/// let Some(Event::Value { text, .. }) = parser.next() else { panic!() };
+/// assert_eq!("127.0.0.1", text);
+/// let Some(Event::BlankLine { span }) = parser.next() else { panic!() };
+/// assert_eq!(3, span.start().line());
+/// let Some(Event::Key { text, delimiter, .. }) = parser.next() else { panic!() };
+/// assert_eq!("port", text);
+/// assert_eq!("\"", delimiter.unwrap().open());
+/// let Some(Event::Value { text, .. }) = parser.next() else { panic!() };
+/// assert_eq!("54321", text);
+/// assert_eq!(None, parser.next());
+/// ```
+pub struct Parser<'a, R: BufRead = io::Empty> {
+  options: &'a LoaderOptions,
+  /// Parsed string, texts of keys and values are borrowed from it.
+  input: Option<&'a str>,
+  scanner: Scanner<Source<'a, R>>,
+  state: State,
+  buffer: String,
+  /// Delimiters of the current multiline key or value.
+  delimiter: Delimiter,
+  /// Position of the currently processed character.
+  position: Position,
+  /// Position of the character following the currently processed character.
+  next_position: Position,
+  /// Position of the first character in the current line.
+  line_start: Position,
+  /// Span of the currently buffered key or value.
+  span: Span,
+  /// Span of the last key.
+  key_span: Span,
+  /// Span of the text following the last marker in a multiline key or value
+  /// that was not recognized as a closing marker.
+  trailing: Option<Span>,
+  /// Escape sequence being decoded in a multiline key or value, starting with a backslash.
+  escape: String,
+  /// Position of the backslash starting the escape sequence being decoded.
+  escape_start: Position,
+  /// Length of the buffer after the last decoded escape sequence, decoded characters never close a multiline key or value.
+  escape_end: usize,
+  /// Range of the currently buffered single-line key or value without leading and trailing whitespaces,
+  /// [None] when only whitespaces are buffered.
+  content: Option<Range<usize>>,
+  /// Style of the first line ending in the input.
+  line_ending: Option<LineEnding>,
+  /// Flag set when the next line ending terminates the line with a closing marker.
+  closed: bool,
+  /// State resumed after the comment line.
+  resume: State,
+  /// Length of the prefix of the current comment.
+  prefix_len: usize,
+  /// Event ready to be returned.
+  event: Option<Event<'a>>,
+  /// Events reported in the same step after the ready event, rarely more than one event is reported in a single step.
+  pending: Vec<Event<'a>>,
+  /// Error that stopped reading the input.
+  io_error: Option<io::Error>,
+  /// Flag set when parsing is finished.
+  finished: bool,
+}
+
+impl<'a> Parser<'a> {
+  /// Creates a parser over the specified string, using default options.
+  pub fn new(input: &'a str) -> Self {
+    Self::with_options(input, LoaderOptions::shared())
+  }
+
+  /// Creates a parser over the specified string, using the specified options.
+  pub(crate) fn with_options(input: &'a str, options: &'a LoaderOptions) -> Self {
+    Self::create(Some(input), Source::Str(input.chars()), options)
+  }
+}
+
+impl<'a, R: BufRead> Parser<'a, R> {
+  /// Creates a parser over the specified buffered reader, using default options.
+  ///
+  /// I/O errors are reported as [Event::Error] with [ErrorKind::Io], parsing stops after such an error.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{Event, Parser};
+  ///
+  /// let parser = Parser::from_reader("a\nb\nc\nd\n".as_bytes());
+  /// let keys = parser.filter_map(|event| match event {
+  ///   Event::Key { text, .. } => Some(text.into_owned()),
+  ///   _ => None,
+  /// });
+  /// assert_eq!("a,c", keys.collect::<Vec<String>>().join(","));
+  /// ```
+  pub fn from_reader(reader: R) -> Self {
+    Self::from_reader_with_options(reader, LoaderOptions::shared())
+  }
+
+  /// Creates a parser over the specified buffered reader, using the specified options.
+  pub(crate) fn from_reader_with_options(reader: R, options: &'a LoaderOptions) -> Self {
+    Self::create(None, Source::Reader(ReaderChars::new(reader)), options)
+  }
+
+  fn create(input: Option<&'a str>, source: Source<'a, R>, options: &'a LoaderOptions) -> Self {
+    Self {
+      options,
+      input,
+      scanner: Scanner::new(source),
+      state: State::Key,
+      buffer: String::new(),
+      delimiter: Delimiter::none(),
+      position: Position::start(),
+      next_position: Position::start(),
+      line_start: Position::start(),
+      span: Span::new(Position::start(), Position::start()),
+      key_span: Span::new(Position::start(), Position::start()),
+      trailing: None,
+      escape: String::new(),
+      escape_start: Position::start(),
+      escape_end: 0,
+      content: None,
+      line_ending: None,
+      closed: false,
+      resume: State::Key,
+      prefix_len: 0,
+      event: None,
+      pending: Vec::new(),
+      io_error: None,
+      finished: false,
+    }
+  }
+
+  /// Returns the error that stopped reading the input, if any.
+  pub(crate) fn take_io_error(&mut self) -> Option<io::Error> {
+    self.io_error.take()
+  }
+
+  /// Returns the style of the first line ending parsed so far, or [None] when no line ending was parsed yet.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::{LineEnding, Parser};
+  ///
+  /// let mut parser = Parser::from_reader("a\r\nb\nc\r\nd\r\n".as_bytes());
+  /// assert_eq!(None, parser.line_ending());
+  /// assert_eq!(4, parser.by_ref().count());
+  /// assert_eq!(Some(LineEnding::CrLf), parser.line_ending());
+  /// ```
+  pub fn line_ending(&self) -> Option<LineEnding> {
+    self.line_ending
+  }
+
+  /// Processes the next character of the input.
+  fn step(&mut self) {
+    if self.scanner.skip_bom() {
+      self.line_start = self.scanner.position();
+    }
+    self.position = self.scanner.position();
+    let Some(current_char) = self.scanner.next_char() else {
+      self.finish();
+      return;
+    };
+    self.next_position = self.scanner.position();
+    if current_char == LF && self.line_ending.is_none() {
+      self.line_ending = Some(self.scanner.line_ending());
+    }
+    let next_char = self.scanner.peek_char();
+    match self.state {
+      State::Key => match (current_char, next_char) {
+        (ch, _) if self.opening_delimiter(ch).is_some() => {
+          self.open(ch);
+          self.clear_buffer(State::KeyExt);
+        }
+        (LF, _) if self.is_heredoc_opening() => self.open_heredoc(State::KeyExt),
+        (LF, _) => self.consume_non_empty_key(),
+        (ch, _) => {
+          self.consume_char(ch);
+          self.check_comment();
+        }
+      },
+      State::KeyExt => match (current_char, next_char) {
+        (ch, _) if self.consume_escape(ch) => {}
+        (LF, _) if self.is_heredoc_terminator() => {
+          self.close_heredoc();
+          self.consume_key(true);
+        }
+        (ch, Some(LF)) if self.is_closing_delimiter(ch) => {
+          self.close(ch);
+          self.consume_key(true);
+        }
+        (ch, next) if self.is_closing_delimiter(ch) => self.consume_marker(ch, next),
+        (LF, _) if self.options.preserve_line_endings => self.consume_line_ending(),
+        (ch, _) => self.consume_char(ch),
+      },
+      State::Value => match (current_char, next_char) {
+        (ch, _) if self.opening_delimiter(ch).is_some() => {
+          self.open(ch);
+          self.clear_buffer(State::ValueExt);
+        }
+        (LF, _) if self.is_heredoc_opening() => self.open_heredoc(State::ValueExt),
+        (LF, _) => self.consume_non_empty_value(),
+        (ch1, ch2) => {
+          self.consume_char(ch1);
+          self.check_comment();
+          if ch2.is_none() && !self.finished && matches!(self.state, State::Value) {
+            self.consume_non_empty_value();
+          }
+        }
+      },
+      State::ValueExt => match (current_char, next_char) {
+        (ch, _) if self.consume_escape(ch) => {}
+        (LF, _) if self.is_heredoc_terminator() => {
+          self.close_heredoc();
+          self.consume_value(true);
+        }
+        (ch, Some(LF)) if self.is_closing_delimiter(ch) => {
+          self.close(ch);
+          self.consume_value(true);
+        }
+        (ch, next) if self.is_closing_delimiter(ch) => self.consume_marker(ch, next),
+        (LF, _) if self.options.preserve_line_endings => self.consume_line_ending(),
+        (ch, _) => self.consume_char(ch),
+      },
+      State::Comment => match current_char {
+        LF => self.consume_comment(),
+        ch => {
+          if !ch.is_whitespace() {
+            self.span.set_end(self.next_position);
+          }
+          self.buffer.push(ch);
+        }
+      },
+    }
+    if current_char == LF {
+      self.line_start = self.next_position;
+    }
+  }
+
+  /// Reports the input that was left unprocessed when the end of input was reached.
+  fn finish(&mut self) {
+    self.finished = true;
+    if let Source::Reader(chars) = self.scanner.source_mut() {
+      if let Some(e) = chars.take_error() {
+        let span = Span::new(self.position, self.position);
+        self.emit(Event::Error {
+          kind: ErrorKind::Io(e.kind()),
+          span,
+        });
+        self.io_error = Some(e);
+        return;
+      }
+    }
+    if !self.escape.is_empty() {
+      self.reject_escape(self.position);
+    }
+    let rest = Span::new(self.span.start(), self.position);
+    match self.state {
+      State::Key if !self.buffer.trim().is_empty() => {
+        self.emit_key(false);
+        self.error(ErrorKind::MissingValue, self.key_span);
+      }
+      State::Key => self.consume_last_blank_line(),
+      State::KeyExt if self.is_heredoc_terminator() => {
+        self.close_heredoc();
+        self.emit_key(true);
+        self.error(ErrorKind::MissingValue, self.key_span);
+      }
+      State::KeyExt => {
+        self.report_trailing();
+        self.error(ErrorKind::UnterminatedKey, rest);
+      }
+      State::Value => {
+        self.consume_last_blank_line();
+        self.error(ErrorKind::MissingValue, self.key_span);
+      }
+      State::ValueExt if self.is_heredoc_terminator() => {
+        self.close_heredoc();
+        self.consume_value(true);
+      }
+      State::ValueExt => {
+        self.report_trailing();
+        self.error(ErrorKind::UnterminatedValue, rest);
+      }
+      State::Comment => {
+        self.consume_comment();
+        if let State::Value = self.state {
+          self.error(ErrorKind::MissingValue, self.key_span);
+        }
+      }
+    }
+  }
+
+  /// Reports the text following the marker that was probably meant to close
+  /// the unterminated multiline key or value.
+  fn report_trailing(&mut self) {
+    if let Some(span) = self.trailing {
+      self.emit(Event::Warning {
+        kind: ErrorKind::TextAfterMarker,
+        span,
+      });
+    }
+  }
+
+  /// Reports an error.
+  fn error(&mut self, kind: ErrorKind, span: Span) {
+    self.emit(Event::Error { kind, span });
+  }
+
+  /// Reports the event, following the events already reported in the current step.
+  fn emit(&mut self, event: Event<'a>) {
+    if self.event.is_none() {
+      self.event = Some(event);
+    } else {
+      self.pending.push(event);
+    }
+  }
+
+  /// Consumes the specified character.
+  fn consume_char(&mut self, ch: char) {
+    if self.buffer.is_empty() {
+      self.content = None;
+    }
+    match self.state {
+      State::Key | State::Value if !ch.is_whitespace() => {
+        if self.content.is_none() {
+          self.span = Span::new(self.position, self.position);
+        }
+        self.span.set_end(self.next_position);
+      }
+      State::KeyExt | State::ValueExt if ch != LF => {
+        if let Some(span) = &mut self.trailing {
+          if span.end() == self.position {
+            span.set_end(self.next_position);
+          }
+        }
+      }
+      _ => {}
+    }
+    self.buffer.push(ch);
+    if matches!(self.state, State::Key | State::Value) && !ch.is_whitespace() {
+      let start = self.content.as_ref().map_or(self.buffer.len() - ch.len_utf8(), |content| content.start);
+      self.content = Some(start..self.buffer.len());
+    }
+    self.check_length();
+  }
+
+  /// Consumes the line break in a multiline key or value, keeping the original line ending.
+  fn consume_line_ending(&mut self) {
+    self.buffer.push_str(self.scanner.line_ending().as_str());
+    self.check_length();
+  }
+
+  /// Stops parsing when the currently buffered key or value exceeds the length limit.
+  ///
+  /// Whitespaces trimmed from single-line keys and values are not counted,
+  /// but the whole buffered line must not exceed the limit by more than [TRIMMED_ALLOWANCE].
+  fn check_length(&mut self) {
+    let (limit, kind) = match self.state {
+      State::Key | State::KeyExt => (self.options.max_key_length, ErrorKind::KeyTooLong),
+      State::Value | State::ValueExt => (self.options.max_value_length, ErrorKind::ValueTooLong),
+      State::Comment => return,
+    };
+    let Some(limit) = limit else {
+      return;
+    };
+    let length = match self.state {
+      State::KeyExt | State::ValueExt => self.buffer.len(),
+      _ => self.trimmed_range().len(),
+    };
+    if length > limit || self.buffer.len() > limit.saturating_add(TRIMMED_ALLOWANCE) {
+      // A single-line key or value consisting of whitespaces only has no span yet.
+      let start = match self.state {
+        State::Key | State::Value if self.content.is_none() => self.next_position.retreat(&self.buffer),
+        _ => self.span.start(),
+      };
+      self.error(kind, Span::new(start, self.next_position));
+      self.finished = true;
+    }
+  }
+
+  /// Consumes the specified character when it starts or continues an escape sequence,
+  /// returns `false` when the character should be processed as usual.
+  ///
+  /// Escape sequences are recognized only when enabled, in multiline keys and values other than heredocs.
+  fn consume_escape(&mut self, ch: char) -> bool {
+    if !self.options.escapes || self.delimiter.is_heredoc() {
+      return false;
+    }
+    if self.escape.is_empty() {
+      if ch != '\\' {
+        return false;
+      }
+      self.escape_start = self.position;
+      self.escape.push(ch);
+      return true;
+    }
+    let decoded = match (&self.escape[1..], ch) {
+      ("", LF) => None,
+      ("", 'n') => Some(LF),
+      ("", 't') => Some('\t'),
+      ("", '\\' | '"') => Some(ch),
+      ("", ch) if self.delimiter.open().contains(ch) || self.delimiter.close().contains(ch) => Some(ch),
+      ("", 'u') | ("u", '{') => {
+        self.escape.push(ch);
+        return true;
+      }
+      ("", _) => {
+        self.escape.push(ch);
+        self.reject_escape(self.next_position);
+        return true;
+      }
+      (digits, ch) if digits.starts_with("u{") && ch.is_ascii_hexdigit() && digits.len() < 8 => {
+        self.escape.push(ch);
+        return true;
+      }
+      (digits, '}') if digits.starts_with("u{") => {
+        let decoded = u32::from_str_radix(&digits[2..], 16).ok().and_then(char::from_u32);
+        if decoded.is_none() {
+          self.escape.push(ch);
+          self.reject_escape(self.next_position);
+          return true;
+        }
+        decoded
+      }
+      _ => None,
+    };
+    match decoded {
+      Some(decoded) => {
+        self.escape.clear();
+        self.consume_char(decoded);
+        self.escape_end = self.buffer.len();
+        true
+      }
+      None => {
+        // The character does not belong to the rejected escape sequence, so it is processed as usual.
+        self.reject_escape(self.position);
+        self.consume_escape(ch)
+      }
+    }
+  }
+
+  /// Reports the escape sequence being decoded as invalid and buffers it verbatim.
+  fn reject_escape(&mut self, end: Position) {
+    self.error(ErrorKind::InvalidEscape, Span::new(self.escape_start, end));
+    self.buffer.push_str(&mem::take(&mut self.escape));
+    self.escape_end = self.buffer.len();
+    self.check_length();
+  }
+
+  /// Consumes the last character of the closing delimiter that is not followed by a newline,
+  /// so it does not close the multiline key or value.
+  fn consume_marker(&mut self, ch: char, next_char: Option<char>) {
+    self.consume_char(ch);
+    self.trailing = next_char.is_some().then(|| Span::new(self.next_position, self.next_position));
+  }
+
+  /// Starts multiline key or value with the opening delimiter ending with the specified character.
+  fn open(&mut self, ch: char) {
+    let Some(delimiter) = self.opening_delimiter(ch).cloned() else {
+      return;
+    };
+    let prefix = &delimiter.open()[..delimiter.open().len() - ch.len_utf8()];
+    self.span = Span::new(self.position.retreat(prefix), self.next_position);
+    self.delimiter = delimiter;
+    self.trailing = None;
+  }
+
+  /// Starts multiline key or value with the heredoc opening line, switches to the specified state.
+  fn open_heredoc(&mut self, next_state: State) {
+    self.delimiter = Delimiter::heredoc(&self.buffer.trim()[HEREDOC.len()..]);
+    self.span.set_end(self.position);
+    self.trailing = None;
+    self.clear_buffer(next_state);
+  }
+
+  /// Closes multiline key or value with the heredoc terminator line.
+  fn close_heredoc(&mut self) {
+    let text = &self.buffer[..self.buffer.len() - self.delimiter.close().len()];
+    let len = text.strip_suffix("\r\n").or_else(|| text.strip_suffix([LF, CR])).unwrap_or(text).len();
+    self.buffer.truncate(len);
+    self.span.set_end(self.position);
+  }
+
+  /// Closes multiline key or value with the closing delimiter ending with the specified character.
+  fn close(&mut self, ch: char) {
+    let prefix_len = self.delimiter.close().len() - ch.len_utf8();
+    self.buffer.truncate(self.buffer.len() - prefix_len);
+    self.span.set_end(self.next_position);
+    self.trailing = None;
+    self.closed = true;
+  }
+
+  /// Starts a comment when the current line, without leading whitespaces, is a comment prefix.
+  fn check_comment(&mut self) {
+    if self.finished || self.options.comments.is_empty() || self.buffer.ends_with(char::is_whitespace) {
+      return;
+    }
+    // The text following leading whitespaces starts at the beginning of the current span.
+    let len = self.next_position.offset() - self.span.start().offset();
+    let Some(text) = self.buffer.len().checked_sub(len).and_then(|start| self.buffer.get(start..)) else {
+      return;
+    };
+    if self.options.comments.iter().any(|prefix| prefix == text) {
+      self.prefix_len = text.len();
+      self.resume = self.state;
+      self.state = State::Comment;
+    }
+  }
+
+  /// Reports the current comment and resumes the state preceding the comment.
+  fn consume_comment(&mut self) {
+    let text = match self.input {
+      Some(input) => Cow::Borrowed(input[self.span.range()][self.prefix_len..].trim()),
+      None => Cow::Owned(self.buffer.trim()[self.prefix_len..].trim().to_string()),
+    };
+    self.emit(Event::Comment { text, span: self.span });
+    self.buffer.clear();
+    self.state = self.resume;
+  }
+
+  /// Consumes the line ending, reports a blank line when the current line contains only whitespaces.
+  fn consume_blank_line(&mut self) {
+    if !mem::take(&mut self.closed) {
+      self.emit(Event::BlankLine {
+        span: Span::new(self.line_start, self.position),
+      });
+    }
+    self.buffer.clear();
+  }
+
+  /// Reports the last line of the input when it contains only whitespaces.
+  fn consume_last_blank_line(&mut self) {
+    if !self.buffer.is_empty() {
+      self.consume_blank_line();
+    }
+  }
+
+  fn consume_key(&mut self, multiline: bool) {
+    self.emit_key(multiline);
+    self.state = State::Value;
+  }
+
+  fn emit_key(&mut self, multiline: bool) {
+    let text = self.take_text(multiline);
+    let delimiter = self.take_delimiter(multiline);
+    self.key_span = self.span;
+    self.emit(Event::Key { text, span: self.span, delimiter });
+  }
+
+  fn consume_non_empty_key(&mut self) {
+    if self.buffer.trim().is_empty() {
+      self.consume_blank_line();
+    } else {
+      self.consume_key(false);
+    }
+  }
+
+  fn consume_non_empty_value(&mut self) {
+    if self.buffer.trim().is_empty() {
+      self.consume_blank_line();
+    } else {
+      self.consume_value(false);
+    }
+  }
+
+  fn consume_value(&mut self, multiline: bool) {
+    let text = self.take_text(multiline);
+    let delimiter = self.take_delimiter(multiline);
+    self.emit(Event::Value { text, span: self.span, delimiter });
+    self.state = State::Key;
+  }
+
+  /// Returns the text of the currently buffered key or value.
+  ///
+  /// The text is borrowed from the parsed string when it is the same as the source text,
+  /// the buffer is then cleared and reused for the next key or value.
+  fn take_text(&mut self, multiline: bool) -> Cow<'a, str> {
+    if multiline {
+      if let Some(text) = self.reshape() {
+        self.buffer.clear();
+        return Cow::Owned(text);
+      }
+    }
+    if !multiline && self.is_empty_value() {
+      self.trim_single_line();
+      self.buffer.clear();
+      return Cow::Borrowed("");
+    }
+    let trimmed = if multiline { 0..self.buffer.len() } else { self.trim_single_line() };
+    if let Some(input) = self.input {
+      let range = self.span.range();
+      let source = if multiline {
+        let source = &input[range.start + self.delimiter.open().len()..range.end - self.delimiter.close().len()];
+        if self.delimiter.is_heredoc() {
+          strip_line_endings(source)
+        } else {
+          source
+        }
+      } else {
+        &input[range]
+      };
+      if source == &self.buffer[trimmed.clone()] {
+        self.buffer.clear();
+        return Cow::Borrowed(source);
+      }
+    }
+    let text = if multiline { mem::take(&mut self.buffer) } else { self.buffer[trimmed].to_string() };
+    self.buffer.clear();
+    Cow::Owned(text)
+  }
+
+  /// Returns the delimiters of the currently buffered multiline key or value, [None] for a single-line key or value.
+  fn take_delimiter(&mut self, multiline: bool) -> Option<Delimiter> {
+    multiline.then(|| mem::replace(&mut self.delimiter, Delimiter::none()))
+  }
+
+  /// Returns the trim policy of the currently buffered key or value.
+  fn trim_policy(&self) -> TrimPolicy {
+    match self.state {
+      State::Key | State::KeyExt => self.options.key_trim,
+      _ => self.options.value_trim,
+    }
+  }
+
+  /// Returns the range of the currently buffered single-line key or value, trimmed according to the trim policy.
+  fn trimmed_range(&self) -> Range<usize> {
+    let policy = self.trim_policy();
+    let len = self.buffer.len();
+    let content = self.content_range();
+    let start = if policy.trims_start() { content.start } else { 0 };
+    let end = if policy.trims_end() { content.end } else { len };
+    start..end.max(start)
+  }
+
+  /// Returns the range of the currently buffered single-line key or value without leading and trailing whitespaces.
+  fn content_range(&self) -> Range<usize> {
+    let len = self.buffer.len();
+    self.content.clone().filter(|_| len > 0).unwrap_or(len..len)
+  }
+
+  /// Returns the range of the currently buffered single-line key or value, trimmed according to the trim policy,
+  /// and extends the span, covering the trimmed text only, to the preserved whitespaces.
+  fn trim_single_line(&mut self) -> Range<usize> {
+    let range = self.trimmed_range();
+    let Range { start: leading, end: trailing } = self.content_range();
+    if range.start < leading {
+      self.span = Span::new(self.span.start().retreat(&self.buffer[range.start..leading]), self.span.end());
+    }
+    if range.end > trailing {
+      let end = self.buffer[trailing..range.end].chars().fold(self.span.end(), Position::advance);
+      self.span.set_end(end);
+    }
+    range
+  }
+
+  /// Returns `true` when the currently buffered single-line value is the token representing an empty value.
+  fn is_empty_value(&self) -> bool {
+    matches!(self.state, State::Value) && self.options.empty_value.as_deref().is_some_and(|token| token == &self.buffer[self.content_range()])
+  }
+
+  /// Returns the text of the currently buffered multiline key or value with stripped indentation
+  /// and trimmed blank lines, or [None] when the text does not change.
+  fn reshape(&self) -> Option<String> {
+    let options = &self.options;
+    if options.dedent == Dedent::None && !options.trim_first_blank_line && !options.trim_last_blank_line {
+      return None;
+    }
+    let heredoc = self.delimiter.is_heredoc();
+    let column = if heredoc {
+      self.span.start().column()
+    } else {
+      self.span.start().column() + self.delimiter.open().chars().count()
+    };
+    reshape(&self.buffer, options.dedent, options.trim_first_blank_line, options.trim_last_blank_line, column, heredoc)
+  }
+
+  /// Clears the input buffer and switches to the next state.
+  fn clear_buffer(&mut self, next_state: State) {
+    self.buffer.clear();
+    self.escape_end = 0;
+    self.state = next_state;
+  }
+
+  /// Returns the delimiter opened by the specified character,
+  /// preceding characters of the opening delimiter are already buffered.
+  ///
+  /// With strict markers, a delimiter is opened only when it is the first non-whitespace text in the line.
+  fn opening_delimiter(&self, ch: char) -> Option<&Delimiter> {
+    let delimiters = match self.state {
+      State::Key => &self.options.key_delimiters,
+      _ => &self.options.value_delimiters,
+    };
+    delimiters.iter().find(|delimiter| {
+      let Some(prefix) = delimiter.open().strip_suffix(ch) else {
+        return false;
+      };
+      self.buffer.ends_with(prefix) && (!self.options.strict_markers || self.is_blank_before(self.buffer.len() - prefix.len()))
+    })
+  }
+
+  /// Returns `true` when the currently buffered single-line key or value contains only whitespaces before the specified offset.
+  fn is_blank_before(&self, offset: usize) -> bool {
+    self.buffer.is_empty() || self.content.as_ref().is_none_or(|content| content.start >= offset)
+  }
+
+  /// Returns `true` when specified character ends the current closing delimiter,
+  /// preceding characters of the closing delimiter are already buffered and none of them is decoded from an escape sequence.
+  fn is_closing_delimiter(&self, ch: char) -> bool {
+    !self.delimiter.is_heredoc()
+      && self
+        .delimiter
+        .close()
+        .strip_suffix(ch)
+        .is_some_and(|prefix| self.buffer.ends_with(prefix) && self.buffer.len() - prefix.len() >= self.escape_end)
+  }
+
+  /// Returns `true` when heredocs are enabled and the current line, without surrounding whitespaces,
+  /// consists of `<<` followed by the name of the terminator.
+  fn is_heredoc_opening(&self) -> bool {
+    self.options.heredoc && is_heredoc_opening(&self.buffer)
+  }
+
+  /// Returns `true` when the current line of the heredoc consists exactly of the terminator.
+  fn is_heredoc_terminator(&self) -> bool {
+    self.delimiter.is_heredoc() && self.buffer.rsplit([LF, CR]).next() == Some(self.delimiter.close())
+  }
+}
+
+/// Returns `true` when the line, without leading and trailing whitespaces, opens a heredoc.
+pub(crate) fn is_heredoc_opening(line: &str) -> bool {
+  line.trim().strip_prefix(HEREDOC).is_some_and(is_heredoc_name)
+}
+
+/// Returns `true` when the text is a valid name of the heredoc terminator.
+fn is_heredoc_name(text: &str) -> bool {
+  !text.is_empty() && text.chars().all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '-')
+}
+
+/// Returns the text without the leading and trailing line ending.
+fn strip_line_endings(text: &str) -> &str {
+  let text = text.strip_prefix("\r\n").or_else(|| text.strip_prefix([LF, CR])).unwrap_or(text);
+  text.strip_suffix("\r\n").or_else(|| text.strip_suffix([LF, CR])).unwrap_or(text)
+}
+
+impl<'a, R: BufRead> Iterator for Parser<'a, R> {
+  type Item = Event<'a>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while self.event.is_none() && !self.finished {
+      self.step();
+    }
+    let event = self.event.take();
+    if !self.pending.is_empty() {
+      self.event = Some(self.pending.remove(0));
+    }
+    event
+  }
+}
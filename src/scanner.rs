@@ -0,0 +1,186 @@
+//! # Scanner of the KIVI source text
+
+use crate::line_ending::LineEnding;
+use crate::span::Position;
+use std::io::{self, BufRead};
+use std::str;
+
+/// Line feed character (LF).
+pub const LF: char = '\n';
+
+/// Carriage return character (CR).
+pub const CR: char = '\r';
+
+/// Byte order mark (U+FEFF), skipped at the beginning of the source text.
+pub const BOM: char = '\u{FEFF}';
+
+/// Scanner returning characters of the source text with line endings
+/// normalized to LF, keeping track of positions in the original text.
+pub struct Scanner<I: Iterator<Item = char>> {
+  chars: I,
+  peeked: Option<Option<char>>,
+  position: Position,
+  line_ending: LineEnding,
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+  /// Creates a scanner over the specified characters.
+  pub fn new(chars: I) -> Self {
+    Self {
+      chars,
+      peeked: None,
+      position: Position::start(),
+      line_ending: LineEnding::Lf,
+    }
+  }
+
+  /// Returns the source of scanned characters.
+  pub fn source_mut(&mut self) -> &mut I {
+    &mut self.chars
+  }
+
+  /// Returns the position of the next character.
+  pub fn position(&self) -> Position {
+    self.position
+  }
+
+  /// Returns the original line ending of the last line break returned as LF.
+  pub fn line_ending(&self) -> LineEnding {
+    self.line_ending
+  }
+
+  /// Skips the byte order mark at the beginning of the source text, returns `true` when skipped.
+  pub fn skip_bom(&mut self) -> bool {
+    if self.position.offset() != 0 || self.peek() != Some(BOM) {
+      return false;
+    }
+    self.take();
+    self.position = Position::new(BOM.len_utf8(), 1, 1);
+    true
+  }
+
+  /// Returns the next character, or [None] when the end of input was reached.
+  pub fn next_char(&mut self) -> Option<char> {
+    match self.take() {
+      Some(CR) => {
+        if self.peek() == Some(LF) {
+          self.take();
+          self.position = self.position.advance_line(2);
+          self.line_ending = LineEnding::CrLf;
+        } else {
+          self.position = self.position.advance_line(1);
+          self.line_ending = LineEnding::Cr;
+        }
+        Some(LF)
+      }
+      Some(LF) => {
+        self.position = self.position.advance_line(1);
+        self.line_ending = LineEnding::Lf;
+        Some(LF)
+      }
+      Some(ch) => {
+        self.position = self.position.advance(ch);
+        Some(ch)
+      }
+      None => None,
+    }
+  }
+
+  /// Returns the next character without consuming it, or [None] when the end of input was reached.
+  pub fn peek_char(&mut self) -> Option<char> {
+    match self.peek() {
+      Some(CR) => Some(LF),
+      other => other,
+    }
+  }
+
+  /// Takes the next raw character from the source.
+  fn take(&mut self) -> Option<char> {
+    match self.peeked.take() {
+      Some(ch) => ch,
+      None => self.chars.next(),
+    }
+  }
+
+  /// Returns the next raw character from the source without consuming it.
+  fn peek(&mut self) -> Option<char> {
+    *self.peeked.get_or_insert_with(|| self.chars.next())
+  }
+}
+
+/// Iterator over characters decoded incrementally from a buffered reader.
+///
+/// Iteration stops at the first error, the error is retained
+/// and can be retrieved using [ReaderChars::take_error].
+pub struct ReaderChars<R: BufRead> {
+  reader: R,
+  error: Option<io::Error>,
+}
+
+impl<R: BufRead> ReaderChars<R> {
+  /// Creates an iterator over characters read from the specified reader.
+  pub fn new(reader: R) -> Self {
+    Self { reader, error: None }
+  }
+
+  /// Returns the error that stopped the iteration, if any.
+  pub fn take_error(&mut self) -> Option<io::Error> {
+    self.error.take()
+  }
+
+  /// Reads the next character, returns `None` when the end of input was reached.
+  fn read_char(&mut self) -> io::Result<Option<char>> {
+    let Some(first) = self.read_byte()? else {
+      return Ok(None);
+    };
+    let width = match first {
+      0x00..=0x7F => return Ok(Some(first as char)),
+      0xC2..=0xDF => 2,
+      0xE0..=0xEF => 3,
+      0xF0..=0xF4 => 4,
+      _ => return Err(invalid_utf8()),
+    };
+    let mut bytes = [first, 0, 0, 0];
+    for byte in bytes.iter_mut().take(width).skip(1) {
+      *byte = self.read_byte()?.ok_or_else(invalid_utf8)?;
+    }
+    str::from_utf8(&bytes[..width]).map(|s| s.chars().next()).map_err(|_| invalid_utf8())
+  }
+
+  /// Reads the next byte, returns `None` when the end of input was reached.
+  fn read_byte(&mut self) -> io::Result<Option<u8>> {
+    let byte = loop {
+      match self.reader.fill_buf() {
+        Ok(buffer) => break buffer.first().copied(),
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        Err(e) => return Err(e),
+      }
+    };
+    if byte.is_some() {
+      self.reader.consume(1);
+    }
+    Ok(byte)
+  }
+}
+
+impl<R: BufRead> Iterator for ReaderChars<R> {
+  type Item = char;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.error.is_some() {
+      return None;
+    }
+    match self.read_char() {
+      Ok(ch) => ch,
+      Err(e) => {
+        self.error = Some(e);
+        None
+      }
+    }
+  }
+}
+
+/// Returns the error reported when the input is not a valid UTF-8.
+fn invalid_utf8() -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+}
@@ -0,0 +1,109 @@
+//! # Positions and spans in the KIVI source text
+
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// A position of a character in the source text.
+///
+/// Lines and columns are counted from 1, columns are counted in characters.
+/// Line endings `\r\n`, `\r` and `\n` all count as a single line break.
+/// Offset is counted in bytes from the beginning of the source text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+  offset: usize,
+  line: usize,
+  column: usize,
+}
+
+impl Position {
+  /// Creates a position at the specified offset, line and column.
+  pub(crate) fn new(offset: usize, line: usize, column: usize) -> Self {
+    Self { offset, line, column }
+  }
+
+  /// Returns the position of the first character in the source text.
+  pub(crate) fn start() -> Self {
+    Self::new(0, 1, 1)
+  }
+
+  /// Returns the position following the specified character placed at this position.
+  pub(crate) fn advance(self, ch: char) -> Self {
+    Self::new(self.offset + ch.len_utf8(), self.line, self.column + 1)
+  }
+
+  /// Returns the position preceding the specified text, placed in the same line.
+  pub(crate) fn retreat(self, text: &str) -> Self {
+    Self::new(self.offset - text.len(), self.line, self.column - text.chars().count())
+  }
+
+  /// Returns the position following the line ending of the specified length in bytes placed at this position.
+  pub(crate) fn advance_line(self, len: usize) -> Self {
+    Self::new(self.offset + len, self.line + 1, 1)
+  }
+
+  /// Returns the byte offset.
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  /// Returns the line number.
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  /// Returns the column number.
+  pub fn column(&self) -> usize {
+    self.column
+  }
+}
+
+impl Display for Position {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "line {}, column {}", self.line, self.column)
+  }
+}
+
+/// A range of the source text, the start position is inclusive, the end position is exclusive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+  start: Position,
+  end: Position,
+}
+
+impl Span {
+  /// Creates a span between the specified positions.
+  pub(crate) fn new(start: Position, end: Position) -> Self {
+    Self { start, end }
+  }
+
+  /// Returns the position of the first character in this span.
+  pub fn start(&self) -> Position {
+    self.start
+  }
+
+  /// Returns the position directly following the last character in this span.
+  pub fn end(&self) -> Position {
+    self.end
+  }
+
+  /// Returns the range of byte offsets covered by this span.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use kivi::load_from_string;
+  ///
+  /// let input = "host\n  127.0.0.1  \n";
+  /// let kvp = load_from_string(input);
+  /// let span = kvp.span_of_value("host").unwrap();
+  /// assert_eq!("127.0.0.1", &input[span.range()]);
+  /// ```
+  pub fn range(&self) -> Range<usize> {
+    self.start.offset..self.end.offset
+  }
+
+  /// Sets the position directly following the last character in this span.
+  pub(crate) fn set_end(&mut self, end: Position) {
+    self.end = end;
+  }
+}
@@ -0,0 +1,366 @@
+//! # Storage of key-value pairs
+
+use crate::model::DuplicatePolicy;
+use crate::span::Span;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::BuildHasher;
+use std::iter::{Enumerate, FusedIterator};
+use std::slice;
+
+/// Key-value pair with spans of the key and the value in the source text and attached comments.
+///
+/// Spans are not available for keys and values that were changed after loading.
+#[derive(Debug, Clone)]
+pub(crate) struct Entry<T> {
+  pub(crate) key: T,
+  pub(crate) value: T,
+  pub(crate) key_span: Option<Span>,
+  pub(crate) value_span: Option<Span>,
+  pub(crate) comments: Vec<T>,
+}
+
+/// Positions of entries with keys having the same hash, in ascending order.
+#[derive(Debug, Clone)]
+enum Slot {
+  One(usize),
+  Many(Vec<usize>),
+}
+
+impl Slot {
+  /// Returns the positions of entries.
+  fn positions(&self) -> &[usize] {
+    match self {
+      Slot::One(position) => slice::from_ref(position),
+      Slot::Many(positions) => positions,
+    }
+  }
+
+  /// Appends the position of an entry following all other entries.
+  fn push(&mut self, position: usize) {
+    match self {
+      Slot::One(first) => *self = Slot::Many(vec![*first, position]),
+      Slot::Many(positions) => positions.push(position),
+    }
+  }
+}
+
+/// Insertion-ordered key-value pairs with a hash index.
+///
+/// Every key and value is stored once, in the ordered vector of entries.
+/// The index maps hashes of keys to positions of all entries with these keys,
+/// the last entry of every key provides the value returned by [get](Self::get).
+#[derive(Clone)]
+pub(crate) struct Table<T> {
+  entries: Vec<Entry<T>>,
+  index: HashMap<u64, Slot>,
+  hasher: RandomState,
+  /// Number of distinct keys.
+  keys: usize,
+}
+
+impl<T: AsRef<str>> Table<T> {
+  /// Creates an empty table.
+  pub(crate) fn new() -> Self {
+    Self {
+      entries: vec![],
+      index: HashMap::new(),
+      hasher: RandomState::new(),
+      keys: 0,
+    }
+  }
+
+  /// Returns the hash of the specified key.
+  fn hash(&self, key: &str) -> u64 {
+    self.hasher.hash_one(key)
+  }
+
+  /// Returns the position of the entry providing the value of the specified key.
+  pub(crate) fn position(&self, key: &str) -> Option<usize> {
+    self.positions(key).next_back()
+  }
+
+  /// Returns the ascending positions of all entries with the specified key.
+  pub(crate) fn positions<'a>(&'a self, key: &'a str) -> impl DoubleEndedIterator<Item = usize> + 'a {
+    self.find_all(self.hash(key), key)
+  }
+
+  /// Returns the ascending positions of all entries with the specified key having the specified hash.
+  fn find_all<'a>(&'a self, hash: u64, key: &'a str) -> impl DoubleEndedIterator<Item = usize> + 'a {
+    let positions = self.index.get(&hash).map_or(&[][..], Slot::positions);
+    positions.iter().copied().filter(move |position| self.entries[*position].key.as_ref() == key)
+  }
+
+  /// Returns the position of the entry providing the value of the specified key having the specified hash.
+  fn find(&self, hash: u64, key: &str) -> Option<usize> {
+    self.find_all(hash, key).next_back()
+  }
+
+  /// Adds the entry at the specified position to the index, following all entries with the same key.
+  fn reindex(&mut self, position: usize) {
+    self.reindex_hashed(self.hash(self.entries[position].key.as_ref()), position);
+  }
+
+  /// Adds the entry at the specified position, with the key having the specified hash, to the index,
+  /// following all entries with the same key.
+  fn reindex_hashed(&mut self, hash: u64, position: usize) {
+    if self.find(hash, self.entries[position].key.as_ref()).is_none() {
+      self.keys += 1;
+    }
+    self.index.entry(hash).and_modify(|slot| slot.push(position)).or_insert(Slot::One(position));
+  }
+
+  /// Returns all entries with the specified key, in document order.
+  pub(crate) fn get_all<'a>(&'a self, key: &'a str) -> impl DoubleEndedIterator<Item = &'a Entry<T>> + 'a {
+    self.positions(key).map(|position| &self.entries[position])
+  }
+
+  /// Returns `true` when the specified key is present.
+  pub(crate) fn contains_key(&self, key: &str) -> bool {
+    self.position(key).is_some()
+  }
+
+  /// Returns the entry providing the value of the specified key.
+  pub(crate) fn get(&self, key: &str) -> Option<&Entry<T>> {
+    self.position(key).map(|index| &self.entries[index])
+  }
+
+  /// Returns the number of entries.
+  pub(crate) fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns ordered entries.
+  pub(crate) fn entries(&self) -> &[Entry<T>] {
+    &self.entries
+  }
+
+  /// Returns ordered entries providing values of their keys, each key is returned once.
+  pub(crate) fn primary_entries(&self) -> PrimaryEntries<'_, T> {
+    PrimaryEntries {
+      table: self,
+      entries: self.entries.iter().enumerate(),
+      remaining: self.keys,
+    }
+  }
+
+  /// Consumes the table, returning ordered entries.
+  pub(crate) fn into_entries(self) -> Vec<Entry<T>> {
+    self.entries
+  }
+
+  /// Consumes the table, returning ordered entries providing values of their keys.
+  pub(crate) fn into_primary_entries(mut self) -> Vec<Entry<T>> {
+    if self.keys < self.entries.len() {
+      let primary = (0..self.entries.len())
+        .map(|index| self.position(self.entries[index].key.as_ref()) == Some(index))
+        .collect::<Vec<bool>>();
+      let mut primary = primary.into_iter();
+      self.entries.retain(|_| primary.next().unwrap_or_default());
+    }
+    self.entries
+  }
+
+  /// Adds a key-value pair with attached comments, the duplicated key is resolved according to the specified policy.
+  pub(crate) fn add(&mut self, key: T, value: T, spans: (Option<Span>, Option<Span>), comments: Vec<T>, policy: DuplicatePolicy) {
+    let hash = self.hash(key.as_ref());
+    match self.find(hash, key.as_ref()) {
+      Some(_) if matches!(policy, DuplicatePolicy::FirstWins | DuplicatePolicy::Reject) => {}
+      Some(index) if policy == DuplicatePolicy::LastWins => {
+        let entry = &mut self.entries[index];
+        entry.value = value;
+        entry.key_span = spans.0;
+        entry.value_span = spans.1;
+        entry.comments = comments;
+      }
+      _ => self.push(
+        hash,
+        Entry {
+          key,
+          value,
+          key_span: spans.0,
+          value_span: spans.1,
+          comments,
+        },
+      ),
+    }
+  }
+
+  /// Appends the entry, with the key having the specified hash, at the end of the table.
+  fn push(&mut self, hash: u64, entry: Entry<T>) {
+    self.entries.push(entry);
+    self.reindex_hashed(hash, self.entries.len() - 1);
+  }
+
+  /// Rebuilds the index after changing positions or keys of entries.
+  fn rebuild_index(&mut self) {
+    self.index.clear();
+    self.keys = 0;
+    for position in 0..self.entries.len() {
+      self.reindex(position);
+    }
+  }
+
+  /// Sets the value of the specified key, the key is appended when not present.
+  pub(crate) fn insert(&mut self, key: T, value: T) -> Option<T> {
+    let hash = self.hash(key.as_ref());
+    match self.find(hash, key.as_ref()) {
+      Some(index) => Some(self.replace_value(index, value)),
+      None => {
+        self.push(
+          hash,
+          Entry {
+            key,
+            value,
+            key_span: None,
+            value_span: None,
+            comments: vec![],
+          },
+        );
+        None
+      }
+    }
+  }
+
+  /// Sets the value of the specified key, only when the key is present.
+  pub(crate) fn set(&mut self, key: &str, value: T) -> Option<T> {
+    self.position(key).map(|index| self.replace_value(index, value))
+  }
+
+  /// Replaces the value of the entry at the specified position, returns the previous value.
+  fn replace_value(&mut self, index: usize, value: T) -> T {
+    let entry = &mut self.entries[index];
+    entry.value_span = None;
+    std::mem::replace(&mut entry.value, value)
+  }
+
+  /// Removes all occurrences of the specified key, returns the value provided by the removed key.
+  pub(crate) fn remove(&mut self, key: &str) -> Option<T> {
+    let position = self.position(key)?;
+    let mut value = None;
+    let entries = std::mem::take(&mut self.entries);
+    for (index, entry) in entries.into_iter().enumerate() {
+      if index == position {
+        value = Some(entry.value);
+      } else if entry.key.as_ref() != key {
+        self.entries.push(entry);
+      }
+    }
+    self.rebuild_index();
+    value
+  }
+
+  /// Renames all occurrences of the specified key, keeping their positions.
+  ///
+  /// Returns `false` when the key is not present or the new key is already present.
+  pub(crate) fn rename(&mut self, key: &str, new_key: T) -> bool
+  where
+    T: Clone,
+  {
+    if !self.contains_key(key) || (self.contains_key(new_key.as_ref()) && key != new_key.as_ref()) {
+      return false;
+    }
+    for entry in self.entries.iter_mut().filter(|entry| entry.key.as_ref() == key) {
+      entry.key = new_key.clone();
+      entry.key_span = None;
+    }
+    self.rebuild_index();
+    true
+  }
+
+  /// Retains only the entries for which the predicate returns `true`.
+  pub(crate) fn retain<F: FnMut(&str, &str) -> bool>(&mut self, mut f: F) {
+    let len = self.entries.len();
+    self.entries.retain(|entry| f(entry.key.as_ref(), entry.value.as_ref()));
+    if self.entries.len() != len {
+      self.rebuild_index();
+    }
+  }
+
+  /// Removes all entries.
+  pub(crate) fn clear(&mut self) {
+    self.entries.clear();
+    self.index.clear();
+    self.keys = 0;
+  }
+
+  /// Converts keys and values of all entries, keeping the index.
+  pub(crate) fn map<U, F: Fn(T) -> U>(self, f: F) -> Table<U> {
+    Table {
+      entries: self
+        .entries
+        .into_iter()
+        .map(|entry| Entry {
+          key: f(entry.key),
+          value: f(entry.value),
+          key_span: entry.key_span,
+          value_span: entry.value_span,
+          comments: entry.comments.into_iter().map(&f).collect(),
+        })
+        .collect(),
+      index: self.index,
+      hasher: self.hasher,
+      keys: self.keys,
+    }
+  }
+}
+
+/// Iterator over ordered entries providing values of their keys, see [Table::primary_entries].
+pub(crate) struct PrimaryEntries<'a, T> {
+  table: &'a Table<T>,
+  entries: Enumerate<slice::Iter<'a, Entry<T>>>,
+  remaining: usize,
+}
+
+impl<'a, T: AsRef<str>> Iterator for PrimaryEntries<'a, T> {
+  type Item = &'a Entry<T>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (_, entry) = self.entries.find(|(index, entry)| self.table.position(entry.key.as_ref()) == Some(*index))?;
+    self.remaining -= 1;
+    Some(entry)
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.remaining, Some(self.remaining))
+  }
+}
+
+impl<T: AsRef<str>> ExactSizeIterator for PrimaryEntries<'_, T> {}
+
+impl<T> Clone for PrimaryEntries<'_, T> {
+  fn clone(&self) -> Self {
+    Self {
+      table: self.table,
+      entries: self.entries.clone(),
+      remaining: self.remaining,
+    }
+  }
+}
+
+impl<T: AsRef<str>> Debug for PrimaryEntries<'_, T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_list().entries(self.clone().map(|entry| (entry.key.as_ref(), entry.value.as_ref()))).finish()
+  }
+}
+
+impl<T: AsRef<str>> FusedIterator for PrimaryEntries<'_, T> {}
+
+impl<T: AsRef<str>> PartialEq for Table<T> {
+  /// Compares ordered keys and values, spans are ignored.
+  fn eq(&self, other: &Self) -> bool {
+    self.entries.len() == other.entries.len()
+      && self
+        .entries
+        .iter()
+        .zip(other.entries.iter())
+        .all(|(a, b)| a.key.as_ref() == b.key.as_ref() && a.value.as_ref() == b.value.as_ref())
+  }
+}
+
+impl<T: AsRef<str>> Debug for Table<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_map().entries(self.entries.iter().map(|entry| (entry.key.as_ref(), entry.value.as_ref()))).finish()
+  }
+}
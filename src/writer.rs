@@ -0,0 +1,302 @@
+//! # Implementation of KIVI serialization functions
+
+use crate::dedent::{reshape, split_lines, Dedent};
+use crate::delimiter::Delimiter;
+use crate::errors::{Error, ErrorKind, Result};
+use crate::line_ending::LineEnding;
+use crate::model::TrimPolicy;
+use crate::options::WriterOptions;
+use crate::parser::is_heredoc_opening;
+use crate::scanner::{BOM, CR, LF};
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counter making names of temporary files unique within the process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Style of formatted keys and values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Style {
+  /// Line ending written in multiline keys and values and around heredocs.
+  pub(crate) line_ending: LineEnding,
+  /// Flag set when texts enclosed in delimiters other than heredocs are escaped.
+  pub(crate) escapes: bool,
+  /// Flag set when line endings in multiline keys and values are written verbatim,
+  /// so texts containing carriage returns can be represented.
+  pub(crate) preserve_line_endings: bool,
+  /// Comment prefixes recognized when the text is loaded back, texts starting with a prefix are enclosed in delimiters.
+  pub(crate) comments: Vec<String>,
+  /// Flag set when heredocs are recognized when the text is loaded back, texts opening a heredoc are enclosed in delimiters.
+  pub(crate) heredoc: bool,
+  /// Policy for stripping the indentation when the text is loaded back, stripped indentation is written.
+  pub(crate) dedent: Dedent,
+  /// Flag set when the blank first line is trimmed when the text is loaded back, a blank line is written before it.
+  pub(crate) trim_first_blank_line: bool,
+  /// Flag set when the blank last line is trimmed when the text is loaded back, a blank line is written after it.
+  pub(crate) trim_last_blank_line: bool,
+  /// Policy for trimming single-line texts when loaded back, trimmed whitespaces are enclosed in delimiters.
+  pub(crate) trim: TrimPolicy,
+  /// Token representing an empty value when loaded back, texts equal to the token are enclosed in delimiters.
+  pub(crate) empty_value: Option<String>,
+}
+
+/// Writes key-value pairs in KIVI format to the specified writer.
+pub(crate) fn write_pairs<'a, W: Write>(mut writer: W, pairs: impl Iterator<Item = (&'a str, &'a str)>, options: &WriterOptions) -> Result<()> {
+  let mut buffer = String::new();
+  format_start(&mut buffer, options);
+  let mut last = None;
+  for (key, value) in pairs {
+    last = Some(format_pair(&mut buffer, key, value, options, last.is_none())?);
+    writer.write_all(buffer.as_bytes())?;
+    buffer.clear();
+  }
+  format_end(&mut buffer, options, last);
+  writer.write_all(buffer.as_bytes())?;
+  writer.flush()?;
+  Ok(())
+}
+
+/// Returns key-value pairs formatted in KIVI format.
+pub(crate) fn format_pairs<'a>(pairs: impl Iterator<Item = (&'a str, &'a str)>, options: &WriterOptions) -> Result<String> {
+  let mut output = String::new();
+  format_start(&mut output, options);
+  let mut last = None;
+  for (key, value) in pairs {
+    last = Some(format_pair(&mut output, key, value, options, last.is_none())?);
+  }
+  format_end(&mut output, options, last);
+  Ok(output)
+}
+
+/// Atomically replaces the file with key-value pairs written in KIVI format.
+///
+/// Key-value pairs are written to a temporary file in the same directory, which is synced
+/// and renamed to the target path, so readers see either the old or the new content.
+pub(crate) fn save_pairs<'a>(path: &Path, pairs: impl Iterator<Item = (&'a str, &'a str)>, options: &WriterOptions) -> Result<()> {
+  let file_name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path does not name a file"))?;
+  let dir = match path.parent() {
+    Some(dir) if !dir.as_os_str().is_empty() => dir,
+    _ => Path::new("."),
+  };
+  let (temp_path, file) = create_temp_file(dir, file_name)?;
+  if let Err(e) = write_temp_file(path, file, pairs, options) {
+    let _ = fs::remove_file(&temp_path);
+    return Err(e);
+  }
+  if options.backup && path.exists() {
+    let mut backup_name = file_name.to_os_string();
+    backup_name.push(".bak");
+    if let Err(e) = fs::copy(path, dir.join(backup_name)) {
+      let _ = fs::remove_file(&temp_path);
+      return Err(e.into());
+    }
+  }
+  if let Err(e) = fs::rename(&temp_path, path) {
+    let _ = fs::remove_file(&temp_path);
+    return Err(e.into());
+  }
+  sync_dir(dir)?;
+  Ok(())
+}
+
+/// Creates a new temporary file in the specified directory.
+fn create_temp_file(dir: &Path, file_name: &OsStr) -> io::Result<(PathBuf, File)> {
+  loop {
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.{}.tmp", process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    let temp_path = dir.join(temp_name);
+    match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+      Ok(file) => return Ok((temp_path, file)),
+      Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+/// Writes key-value pairs to the temporary file, copies permissions of the replaced file and syncs the content.
+fn write_temp_file<'a>(path: &Path, file: File, pairs: impl Iterator<Item = (&'a str, &'a str)>, options: &WriterOptions) -> Result<()> {
+  write_pairs(BufWriter::new(&file), pairs, options)?;
+  if let Ok(metadata) = fs::metadata(path) {
+    file.set_permissions(metadata.permissions())?;
+  }
+  file.sync_all()?;
+  Ok(())
+}
+
+/// Syncs the directory, so the renamed file is persisted.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+  File::open(dir)?.sync_all()
+}
+
+/// Syncing directories is not supported on this platform.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+  Ok(())
+}
+
+/// Appends the byte order mark to the output, when enabled.
+fn format_start(output: &mut String, options: &WriterOptions) {
+  if options.bom {
+    output.push(BOM);
+  }
+}
+
+/// Appends the formatted key-value pair to the output, preceded by the line breaks following the previous pair.
+///
+/// The value is not followed by a line ending, returns `true` when the line ending is required
+/// to close the value enclosed in delimiters.
+fn format_pair(output: &mut String, key: &str, value: &str, options: &WriterOptions, first: bool) -> Result<bool> {
+  let style = options.style();
+  let line_ending = options.line_ending.as_str();
+  if !first {
+    for _ in 0..=options.blank_lines {
+      output.push_str(line_ending);
+    }
+  }
+  output.push_str(&format_raw(key, &options.key_delimiters, None, &style, 1)?.0);
+  output.push_str(line_ending);
+  let (raw, delimiter) = format_raw(value, &options.value_delimiters, None, &style, 1)?;
+  output.push_str(&raw);
+  Ok(delimiter.is_some_and(|delimiter| !delimiter.is_heredoc()))
+}
+
+/// Appends the line ending following the last value, when required or enabled.
+fn format_end(output: &mut String, options: &WriterOptions, last: Option<bool>) {
+  if last.is_some_and(|required| required || options.final_newline) {
+    output.push_str(options.line_ending.as_str());
+  }
+}
+
+/// Returns the key or value formatted as it should appear in the source text, with the chosen delimiter.
+///
+/// The text is written in a single line when it is loaded back unchanged,
+/// otherwise it is enclosed in the first delimiter that keeps it unchanged.
+/// The preferred delimiter is used whenever it keeps the text unchanged, even for single-line texts.
+/// Enclosed texts are written in the specified style, `column` is the column of the opening delimiter.
+pub(crate) fn format_raw(text: &str, delimiters: &[Delimiter], preferred: Option<&Delimiter>, style: &Style, column: usize) -> Result<(String, Option<Delimiter>)> {
+  let enclose = |delimiter: &Delimiter| {
+    let text = shape(text, delimiter, style, column)?;
+    can_enclose(&text, delimiter, style).then(|| (delimiter.clone(), text))
+  };
+  let (delimiter, text) = match preferred.and_then(enclose) {
+    Some(enclosed) => enclosed,
+    None if is_single_line(text, delimiters, style) => return Ok((text.to_string(), None)),
+    None => delimiters.iter().find_map(enclose).ok_or(Error::unpositioned(ErrorKind::Unrepresentable))?,
+  };
+  let text = if style.escapes && !delimiter.is_heredoc() {
+    Cow::Owned(escape(&text, &delimiter))
+  } else {
+    text
+  };
+  let line_ending = style.line_ending.as_str();
+  let text = if style.preserve_line_endings || style.line_ending == LineEnding::Lf {
+    text
+  } else {
+    Cow::Owned(text.replace(LF, line_ending))
+  };
+  let raw = if delimiter.is_heredoc() {
+    format!("{}{}{}{}{}", delimiter.open(), line_ending, text, line_ending, delimiter.close())
+  } else {
+    format!("{}{}{}", delimiter.open(), text, delimiter.close())
+  };
+  Ok((raw, Some(delimiter)))
+}
+
+/// Returns `true` when the text is loaded back unchanged from a single line.
+///
+/// Single-line keys and values are trimmed according to the trim policy, can not be blank and must not contain
+/// line endings or any of the opening delimiters (an opening delimiter starts a multiline key or value anywhere in the line).
+/// Lines starting with a comment prefix are comments, lines opening a heredoc start a multiline key or value
+/// and the token representing an empty value is loaded back as an empty value.
+fn is_single_line(text: &str, delimiters: &[Delimiter], style: &Style) -> bool {
+  !text.trim().is_empty()
+    && (!style.trim.trims_start() || text.trim_start() == text)
+    && (!style.trim.trims_end() || text.trim_end() == text)
+    && style.empty_value.as_deref() != Some(text.trim())
+    && !text.contains([LF, CR])
+    && !delimiters.iter().any(|delimiter| text.contains(delimiter.open()))
+    && !style.comments.iter().any(|prefix| text.trim_start().starts_with(prefix.as_str()))
+    && (!style.heredoc || !is_heredoc_opening(text))
+}
+
+/// Returns `true` when the text is loaded back unchanged after enclosing in the specified delimiter.
+///
+/// Multiline key or value ends with the first closing delimiter followed by a newline,
+/// heredoc ends with the first line consisting of the terminator.
+/// Line endings in multiline keys and values are normalized to LF, unless preserved.
+/// With escapes, any text can be enclosed in delimiters other than heredocs.
+fn can_enclose(text: &str, delimiter: &Delimiter, style: &Style) -> bool {
+  if text.contains(CR) && !style.preserve_line_endings {
+    return false;
+  }
+  if delimiter.is_heredoc() {
+    return !text.split([LF, CR]).any(|line| line == delimiter.close());
+  }
+  style.escapes || find_closing(text, delimiter).is_none()
+}
+
+/// Returns the text enclosed in the specified delimiter, that is loaded back unchanged
+/// after stripping the indentation and trimming blank lines, or [None] when there is no such text.
+///
+/// Continuation lines are indented up to the column of the text following the opening delimiter
+/// and blank first and last lines are preceded and followed by an additional blank line.
+fn shape<'a>(text: &'a str, delimiter: &Delimiter, style: &Style, column: usize) -> Option<Cow<'a, str>> {
+  if style.dedent == Dedent::None && !style.trim_first_blank_line && !style.trim_last_blank_line {
+    return Some(Cow::Borrowed(text));
+  }
+  let heredoc = delimiter.is_heredoc();
+  let column = if heredoc { column } else { column + delimiter.open().chars().count() };
+  let lines = split_lines(text);
+  let blank_first = style.trim_first_blank_line && lines.len() > 1 && lines[0].0.trim().is_empty();
+  let blank_last = style.trim_last_blank_line && lines.len() > 1 && lines[lines.len() - 1].0.trim().is_empty();
+  let start = if heredoc || blank_first { 0 } else { 1 };
+  let indent = if style.dedent == Dedent::Marker { " ".repeat(column - 1) } else { String::new() };
+  let mut shaped = String::with_capacity(text.len());
+  if blank_first {
+    shaped.push(LF);
+  }
+  for (index, (line, ending)) in lines.iter().enumerate() {
+    if index >= start && !line.is_empty() {
+      shaped.push_str(&indent);
+    }
+    shaped.push_str(line);
+    shaped.push_str(ending);
+  }
+  if blank_last {
+    shaped.push(LF);
+  }
+  let loaded = reshape(&shaped, style.dedent, style.trim_first_blank_line, style.trim_last_blank_line, column, heredoc);
+  (loaded.as_deref().unwrap_or(&shaped) == text).then_some(Cow::Owned(shaped))
+}
+
+/// Returns the offset of the first closing delimiter followed by a line ending in the text.
+fn find_closing(text: &str, delimiter: &Delimiter) -> Option<usize> {
+  text
+    .match_indices(delimiter.close())
+    .find(|(index, close)| text[index + close.len()..].starts_with([LF, CR]))
+    .map(|(index, _)| index)
+}
+
+/// Returns the text with escaped backslashes and escaped closing delimiters followed by a line ending,
+/// so it is loaded back unchanged from the multiline key or value enclosed in the specified delimiter.
+fn escape(text: &str, delimiter: &Delimiter) -> String {
+  let text = text.replace('\\', "\\\\");
+  let mut output = String::with_capacity(text.len());
+  let mut rest = text.as_str();
+  while let Some(index) = find_closing(rest, delimiter) {
+    output.push_str(&rest[..index]);
+    output.push('\\');
+    let next = delimiter.close().chars().next().map_or(0, char::len_utf8);
+    output.push_str(&rest[index..index + next]);
+    rest = &rest[index + next..];
+  }
+  output.push_str(rest);
+  output
+}
@@ -1,3 +1,8 @@
 use super::*;
 
 mod test_api;
+mod test_borrowed;
+mod test_editing;
+mod test_multi_values;
+mod test_spans;
+mod test_views;
@@ -0,0 +1,65 @@
+use super::*;
+use kivi::{load_from_string, load_from_string_ref, DuplicatePolicy, ErrorKind, LoaderOptions};
+use std::borrow::Cow;
+
+#[test]
+fn _0001() {
+  // Borrowed key-value pairs are the same as owned key-value pairs.
+  for input in [DATA_PROPERTIES, DATA_001, DATA_002, DATA_003, DATA_004, DATA_005, DATA_006, DATA_TASKS] {
+    assert_eq!(load_from_string(input), load_from_string_ref(input).into_owned());
+  }
+}
+
+#[test]
+fn _0002() {
+  // Duplicated keys are resolved the same way as in owned key-value pairs.
+  for policy in [DuplicatePolicy::FirstWins, DuplicatePolicy::LastWins, DuplicatePolicy::Reject, DuplicatePolicy::KeepAll] {
+    let options = LoaderOptions::new().duplicates(policy);
+    let kvp = options.load_str_ref_with_diagnostics(DATA_TASKS).0;
+    assert_eq!(LoaderOptions::new().duplicates(policy).load_str(DATA_TASKS).unwrap(), kvp.clone().into_owned());
+    assert_eq!(
+      kvp.get_all("Issue1").count(),
+      LoaderOptions::new().duplicates(policy).load_str(DATA_TASKS).unwrap().get_all("Issue1").count()
+    );
+  }
+}
+
+#[test]
+fn _0003() {
+  // Keys and values equal to the source text are borrowed.
+  let kvp = load_from_string_ref("  a  \n  b\n\"c\nd\"\n\"e\"\n");
+  let entries = kvp.into_iter().collect::<Vec<(Cow<str>, Cow<str>)>>();
+  assert!(matches!(&entries[0], (Cow::Borrowed("a"), Cow::Borrowed("b"))));
+  assert!(matches!(&entries[1], (Cow::Borrowed("c\nd"), Cow::Borrowed("e"))));
+}
+
+#[test]
+fn _0004() {
+  // Multiline keys and values with normalized line endings are allocated.
+  let kvp = load_from_string_ref("\"a\r\nb\"\r\n\"c\rd\"\r\ne\r\nf");
+  let entries = kvp.into_iter().collect::<Vec<(Cow<str>, Cow<str>)>>();
+  assert!(matches!(&entries[0], (Cow::Owned(key), Cow::Owned(value)) if key == "a\nb" && value == "c\nd"));
+  assert!(matches!(&entries[1], (Cow::Borrowed("e"), Cow::Borrowed("f"))));
+}
+
+#[test]
+fn _0005() {
+  // Spans and accessors.
+  let input = "a\nb\n\n\"c\nd\"\ne\n";
+  let kvp = load_from_string_ref(input);
+  assert_eq!(2, kvp.len());
+  assert!(!kvp.is_empty());
+  assert_eq!("\"c\nd\"", &input[kvp.span_of_key("c\nd").unwrap().range()]);
+  assert_eq!("b", &input[kvp.span_of_value("a").unwrap().range()]);
+  assert_eq!("a,c\nd", kvp.ordered_keys().collect::<Vec<&str>>().join(","));
+  assert_eq!("b,e", kvp.ordered_values().collect::<Vec<&str>>().join(","));
+  assert_eq!(None, kvp.get("x"));
+  assert!(load_from_string_ref("").is_empty());
+}
+
+#[test]
+fn _0006() {
+  // Strict mode.
+  let options = LoaderOptions::new().strict(true);
+  assert_eq!(&ErrorKind::UnterminatedValue, options.load_str_ref("a\n\"b\n").unwrap_err().kind());
+}
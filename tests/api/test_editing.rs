@@ -0,0 +1,86 @@
+use super::*;
+use kivi::{load_from_string, DuplicatePolicy, KeyValuePairs, LoaderOptions};
+
+fn fmt(kvp: &KeyValuePairs) -> String {
+  kvp.ordered_key_value_pairs().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<String>>().join(",")
+}
+
+#[test]
+fn _0001() {
+  // Building key-value pairs from scratch.
+  let mut kvp = KeyValuePairs::default();
+  assert_eq!(None, kvp.insert("b", "1"));
+  assert_eq!(None, kvp.insert("a", "2"));
+  assert_eq!(Some("1".to_string()), kvp.insert("b", "3"));
+  assert_eq!("b:3,a:2", fmt(&kvp));
+  assert_eq!(KeyValuePairs::new(), KeyValuePairs::default());
+}
+
+#[test]
+fn _0002() {
+  // Replaced keys keep their positions, new keys are appended.
+  let mut kvp = load_from_string(DATA_PROPERTIES);
+  let keys = kvp.ordered_keys().cloned().collect::<Vec<String>>();
+  kvp.insert("host", "0.0.0.0");
+  kvp.set("description", "none");
+  kvp.insert("port", "8080");
+  assert_eq!(keys, kvp.ordered_keys().take(keys.len()).cloned().collect::<Vec<String>>());
+  assert_eq!("0.0.0.0", kvp.get("host").unwrap());
+  assert_eq!("none", kvp.get("description").unwrap());
+  assert_eq!("port", kvp.ordered_keys().next_back().unwrap());
+}
+
+#[test]
+fn _0003() {
+  // Spans of changed keys and values are not reported.
+  let mut kvp = load_from_string("a\nb\nc\nd\n");
+  kvp.set("a", "e");
+  assert!(kvp.span_of_key("a").is_some());
+  assert_eq!(None, kvp.span_of_value("a"));
+  kvp.rename_key("c", "f");
+  assert_eq!(None, kvp.span_of_key("f"));
+  assert!(kvp.span_of_value("f").is_some());
+  kvp.insert("g", "h");
+  assert_eq!(None, kvp.span_of_key("g"));
+}
+
+#[test]
+fn _0004() {
+  // Removing and renaming keys keeps lookup and order in sync.
+  let mut kvp = load_from_string("a\n1\nb\n2\nc\n3\nd\n4\n");
+  assert_eq!(Some("2".to_string()), kvp.remove("b"));
+  assert!(kvp.rename_key("c", "b"));
+  assert_eq!("a:1,b:3,d:4", fmt(&kvp));
+  assert_eq!("3", kvp.get("b").unwrap());
+  assert_eq!(None, kvp.get("c"));
+  assert_eq!("4", kvp.get("d").unwrap());
+  assert!(kvp.rename_key("b", "b"));
+  assert_eq!(kvp, load_from_string("a\n1\nb\n3\nd\n4\n"));
+}
+
+#[test]
+fn _0005() {
+  // Editing keys occurring more than once.
+  let mut kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  assert_eq!(Some("e".to_string()), kvp.insert("a", "f"));
+  assert_eq!("a:b,c:d,a:f", fmt(&kvp));
+  assert!(kvp.rename_key("a", "g"));
+  assert_eq!("b,f", kvp.get_all("g").collect::<Vec<&str>>().join(","));
+  assert_eq!(Some("f".to_string()), kvp.remove("g"));
+  assert_eq!("c:d", fmt(&kvp));
+}
+
+#[test]
+fn _0006() {
+  // Retaining and clearing.
+  let mut kvp = load_from_string("a\n1\nb\n2\nc\n3\nd\n4\n");
+  kvp.retain(|key, value| key != "a" && value != "3");
+  assert_eq!("b:2,d:4", fmt(&kvp));
+  assert_eq!(None, kvp.get("a"));
+  assert_eq!("4", kvp.get("d").unwrap());
+  kvp.clear();
+  assert!(kvp.is_empty());
+  assert_eq!(None, kvp.get("b"));
+  kvp.insert("b", "5");
+  assert_eq!("b:5", fmt(&kvp));
+}
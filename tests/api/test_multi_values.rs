@@ -0,0 +1,85 @@
+use super::*;
+use kivi::{load_from_string, DuplicatePolicy, LoaderOptions};
+
+#[test]
+fn getting_all_values_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(DATA_TASKS).unwrap();
+  assert_eq!(5, kvp.len());
+  assert_eq!(
+    "Build a separate server|Write the documentation\n for the server",
+    kvp.get_all("Issue1").collect::<Vec<&str>>().join("|")
+  );
+  assert_eq!("Develop a new compiler|Review the compiler", kvp.get_all("Issue2").collect::<Vec<&str>>().join("|"));
+  assert_eq!("Prepare the release", kvp.get_all("Issue3").collect::<Vec<&str>>().join("|"));
+  assert_eq!(0, kvp.get_all("Issue4").count());
+}
+
+#[test]
+fn getting_first_and_last_value_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(DATA_TASKS).unwrap();
+  assert_eq!("Build a separate server", kvp.get_first("Issue1").unwrap());
+  assert_eq!("Write the documentation\n for the server", kvp.get_last("Issue1").unwrap());
+  assert_eq!("Prepare the release", kvp.get_first("Issue3").unwrap());
+  assert_eq!("Prepare the release", kvp.get_last("Issue3").unwrap());
+  assert_eq!(None, kvp.get_first("Issue4"));
+  assert_eq!(None, kvp.get_last("Issue4"));
+}
+
+#[test]
+fn getting_value_should_be_backward_compatible() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(DATA_TASKS).unwrap();
+  assert_eq!("Write the documentation\n for the server", kvp.get("Issue1").unwrap());
+  assert_eq!("Review the compiler", kvp.get("Issue2").unwrap());
+  assert_eq!(load_from_string(DATA_TASKS).get("Issue1"), kvp.get("Issue1"));
+}
+
+#[test]
+fn getting_ordered_groups_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(DATA_TASKS).unwrap();
+  assert_eq!(
+    "Issue1:Build a separate server|Write the documentation\n for the server,Issue2:Develop a new compiler|Review the compiler,Issue3:Prepare the release",
+    kvp.ordered_groups().map(|(k, v)| format!("{}:{}", k, v.join("|"))).collect::<Vec<String>>().join(",")
+  );
+}
+
+#[test]
+fn getting_ordered_groups_with_single_values_should_work() {
+  let kvp = load_from_string(DATA_TASKS);
+  assert_eq!(3, kvp.len());
+  assert_eq!(
+    "Issue1:Write the documentation\n for the server,Issue2:Review the compiler,Issue3:Prepare the release",
+    kvp.ordered_groups().map(|(k, v)| format!("{}:{}", k, v.join("|"))).collect::<Vec<String>>().join(",")
+  );
+  assert_eq!(0, load_from_string("").ordered_groups().count());
+}
+
+#[test]
+fn getting_all_values_after_editing_should_work() {
+  let mut kvp = LoaderOptions::new()
+    .duplicates(DuplicatePolicy::KeepAll)
+    .load_str("a\n1\nb\n2\na\n3\nb\n4\na\n5\n")
+    .unwrap();
+  assert_eq!("1,3,5", kvp.get_all("a").collect::<Vec<&str>>().join(","));
+  assert!(kvp.rename_key("b", "c"));
+  assert_eq!("2,4", kvp.get_all("c").collect::<Vec<&str>>().join(","));
+  assert_eq!(0, kvp.get_all("b").count());
+  assert_eq!("5", kvp.remove("a").unwrap());
+  assert_eq!(None, kvp.get_first("a"));
+  kvp.insert("a", "6");
+  assert_eq!("6", kvp.get_first("a").unwrap());
+  assert_eq!(2, kvp.keys().len());
+  assert_eq!(
+    "c:2|4,a:6",
+    kvp.ordered_groups().map(|(k, v)| format!("{}:{}", k, v.join("|"))).collect::<Vec<String>>().join(",")
+  );
+}
+
+#[test]
+fn getting_all_values_of_many_keys_should_work() {
+  let input = (0..20_000).map(|i| format!("key{i}\nfirst\nkey{i}\nsecond\n")).collect::<String>();
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(&input).unwrap();
+  assert_eq!(20_000, kvp.keys().len());
+  assert!((0..20_000).all(|i| kvp.get_all(&format!("key{i}")).eq(["first", "second"])));
+  assert!((0..20_000).all(|i| kvp.get_first(&format!("key{i}")) == Some("first")));
+  assert!(kvp.ordered_groups().all(|(_, values)| values == ["first", "second"]));
+}
@@ -0,0 +1,78 @@
+use super::*;
+use kivi::{load_from_string, load_from_string_markers, DuplicatePolicy, LoaderOptions, Span};
+
+/// Formats span as `offset:line:column-offset:line:column`.
+fn fmt(span: Span) -> String {
+  format!(
+    "{}:{}:{}-{}:{}:{}",
+    span.start().offset(),
+    span.start().line(),
+    span.start().column(),
+    span.end().offset(),
+    span.end().line(),
+    span.end().column()
+  )
+}
+
+#[test]
+fn spans_of_single_line_keys_and_values_should_work() {
+  let kvp = load_from_string(DATA_001);
+  assert_eq!("0:1:1-4:1:5", fmt(kvp.span_of_key("host").unwrap()));
+  assert_eq!("5:2:1-14:2:10", fmt(kvp.span_of_value("host").unwrap()));
+  assert_eq!("16:4:1-20:4:5", fmt(kvp.span_of_key("port").unwrap()));
+  assert_eq!("21:5:1-26:5:6", fmt(kvp.span_of_value("port").unwrap()));
+  assert_eq!("28:7:1-35:7:8", fmt(kvp.span_of_key("timeout").unwrap()));
+  assert_eq!("36:8:1-40:8:5", fmt(kvp.span_of_value("timeout").unwrap()));
+}
+
+#[test]
+fn spans_should_point_to_source_text() {
+  for input in [DATA_001, DATA_002, DATA_003, DATA_004, DATA_005, DATA_006, DATA_PROPERTIES, DATA_TASKS] {
+    let kvp = load_from_string(input);
+    for key in kvp.keys() {
+      let key_span = kvp.span_of_key(key).unwrap();
+      let value_span = kvp.span_of_value(key).unwrap();
+      let raw_key = &input[key_span.range()];
+      let raw_value = &input[value_span.range()];
+      assert!(raw_key == key || raw_key == format!("\"{}\"", key));
+      assert!(raw_value == kvp.get(key).unwrap() || raw_value == format!("\"{}\"", kvp.get(key).unwrap()));
+    }
+  }
+}
+
+#[test]
+fn spans_of_multiline_keys_and_values_should_work() {
+  let input = "  @a\r\nb@\r\n\r\n\t~c\rd~\r";
+  let kvp = load_from_string_markers(input, &['@', '~']);
+  assert_eq!("2:1:3-8:2:3", fmt(kvp.span_of_key("a\nb").unwrap()));
+  assert_eq!("13:4:2-18:5:3", fmt(kvp.span_of_value("a\nb").unwrap()));
+  assert_eq!("@a\r\nb@", &input[kvp.span_of_key("a\nb").unwrap().range()]);
+  assert_eq!("~c\rd~", &input[kvp.span_of_value("a\nb").unwrap().range()]);
+}
+
+#[test]
+fn spans_should_count_columns_in_characters() {
+  let input = "źdźbło\n  żółć";
+  let kvp = load_from_string(input);
+  assert_eq!("0:1:1-9:1:7", fmt(kvp.span_of_key("źdźbło").unwrap()));
+  assert_eq!("12:2:3-20:2:7", fmt(kvp.span_of_value("źdźbło").unwrap()));
+}
+
+#[test]
+fn spans_of_duplicated_keys_should_work() {
+  let input = "a\nb\na\nc\na\nd\n";
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::FirstWins).load_str(input).unwrap();
+  assert_eq!("0:1:1-1:1:2", fmt(kvp.span_of_key("a").unwrap()));
+  assert_eq!("2:2:1-3:2:2", fmt(kvp.span_of_value("a").unwrap()));
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::LastWins).load_str(input).unwrap();
+  assert_eq!("8:5:1-9:5:2", fmt(kvp.span_of_key("a").unwrap()));
+  assert_eq!("10:6:1-11:6:2", fmt(kvp.span_of_value("a").unwrap()));
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(input).unwrap();
+  assert_eq!("8:5:1-9:5:2", fmt(kvp.span_of_key("a").unwrap()));
+  assert_eq!("10:6:1-11:6:2", fmt(kvp.span_of_value("a").unwrap()));
+}
+
+#[test]
+fn comparing_should_ignore_spans() {
+  assert_eq!(load_from_string("a\nb\n"), load_from_string("\n\n   a   \n\n\"b\"\n"));
+}
@@ -0,0 +1,69 @@
+use super::*;
+use kivi::{load_from_string, DuplicatePolicy, LoaderOptions};
+
+#[test]
+fn _0001() {
+  // Unordered views follow document order.
+  let kvp = load_from_string("d\nc\nb\na\nf\ne\n");
+  assert_eq!("d,b,f", kvp.keys().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!("c,a,e", kvp.values().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!("d:c,b:a,f:e", kvp.into_iter().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<String>>().join(","));
+}
+
+#[test]
+fn _0002() {
+  // Unordered views return each key once, with the value returned by `get`.
+  for policy in [DuplicatePolicy::FirstWins, DuplicatePolicy::LastWins, DuplicatePolicy::Reject, DuplicatePolicy::KeepAll] {
+    let kvp = LoaderOptions::new().duplicates(policy).load_str(DATA_TASKS).unwrap();
+    assert_eq!(kvp.keys().count(), kvp.values().count());
+    for (key, value) in kvp.keys().zip(kvp.values()) {
+      assert_eq!(value, kvp.get(key).unwrap());
+    }
+    let pairs = kvp.clone().into_iter().collect::<Vec<(String, String)>>();
+    assert_eq!(kvp.keys().count(), pairs.len());
+    for (key, value) in &pairs {
+      assert_eq!(value, kvp.get(key).unwrap());
+    }
+  }
+}
+
+#[test]
+fn _0003() {
+  // Unordered views follow the occurrences providing values.
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  assert_eq!("c,a", kvp.keys().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!("d,e", kvp.values().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!("a,c,a", kvp.ordered_keys().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!("b,d,e", kvp.ordered_values().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!(3, kvp.len());
+}
+
+#[test]
+fn _0004() {
+  // Many keys.
+  let input = (0..10_000).map(|i| format!("key{}\nvalue{}\n", i, i)).collect::<String>();
+  let kvp = load_from_string(&input);
+  assert_eq!(10_000, kvp.len());
+  for i in 0..10_000 {
+    assert_eq!(&format!("value{}", i), kvp.get(&format!("key{}", i)).unwrap());
+  }
+  assert_eq!(None, kvp.get("key10000"));
+}
+
+#[test]
+fn _0005() {
+  // Views report exact lengths, ordered views iterate from both ends.
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str("a\nb\nc\nd\na\ne\n").unwrap();
+  assert_eq!((2, 2), (kvp.keys().len(), kvp.values().len()));
+  assert_eq!((3, 3), (kvp.ordered_keys().len(), kvp.ordered_values().len()));
+  assert_eq!("a,c,a", kvp.ordered_keys().rev().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  assert_eq!("e,d,b", kvp.ordered_values().rev().map(|s| s.as_str()).collect::<Vec<&str>>().join(","));
+  let mut keys = kvp.keys();
+  keys.next();
+  assert_eq!(1, keys.len());
+  assert_eq!(keys.clone().collect::<Vec<&String>>(), keys.collect::<Vec<&String>>());
+  let mut pairs = kvp.into_iter();
+  assert_eq!(2, pairs.len());
+  assert_eq!(Some(("a".to_string(), "e".to_string())), pairs.next_back());
+  assert_eq!(r#"[("c", "d")]"#, format!("{:?}", pairs));
+}
@@ -0,0 +1,110 @@
+use super::*;
+use kivi::{load_from_string_with_diagnostics, Diagnostic, Severity};
+
+/// Formats diagnostics as `severity[code]:line:column-line:column`.
+fn fmt(diagnostics: &[Diagnostic]) -> String {
+  diagnostics
+    .iter()
+    .map(|d| {
+      let span = d.span();
+      format!(
+        "{}[{}]:{}:{}-{}:{}",
+        d.severity(),
+        d.code(),
+        span.start().line(),
+        span.start().column(),
+        span.end().line(),
+        span.end().column()
+      )
+    })
+    .collect::<Vec<String>>()
+    .join(",")
+}
+
+fn eq(input: &str, expected_len: usize, expected: &str) {
+  let (kvp, diagnostics) = load_from_string_with_diagnostics(input, &['"']);
+  assert_eq!(expected_len, kvp.len());
+  assert_eq!(expected, fmt(&diagnostics));
+}
+
+#[test]
+fn _0001() {
+  // Empty input.
+  eq("", 0, "");
+}
+
+#[test]
+fn _0002() {
+  // Well-formed input.
+  eq(DATA_PROPERTIES, 2, "");
+  eq(DATA_002, 4, "");
+  eq(DATA_006, 3, "");
+}
+
+#[test]
+fn _0003() {
+  // Dangling key at the end of input.
+  eq("a\nb\n  c  \n\n", 1, "error[missing-value]:3:3-3:4");
+}
+
+#[test]
+fn _0004() {
+  // Dangling key at the end of input, not ended with a newline.
+  eq("a\nb\n  cd", 1, "error[missing-value]:3:3-3:5");
+}
+
+#[test]
+fn _0005() {
+  // Dangling multiline key at the end of input.
+  eq("a\nb\n\"c\nd\"\n", 1, "error[missing-value]:3:1-4:3");
+}
+
+#[test]
+fn _0006() {
+  // Multiline key is not closed.
+  eq("\"key", 0, "error[unterminated-key]:1:1-1:5");
+}
+
+#[test]
+fn _0007() {
+  // Multiline value is not closed.
+  eq("\"key\"\n\"value", 0, "error[unterminated-value]:2:1-2:7");
+}
+
+#[test]
+fn _0008() {
+  // Multiline key and non multiline value in one line.
+  eq("\"key\"  value", 0, "warning[text-after-marker]:1:6-1:13,error[unterminated-key]:1:1-1:13");
+}
+
+#[test]
+fn _0009() {
+  // Multiline key not ended with newline after marker.
+  eq("\"key\" \n value", 0, "warning[text-after-marker]:1:6-1:7,error[unterminated-key]:1:1-2:7");
+}
+
+#[test]
+fn _0010() {
+  // Multiline key with multiline value not ended with newline after marker.
+  eq("\"key\"\n \"value\" \r", 0, "warning[text-after-marker]:2:9-2:10,error[unterminated-value]:2:2-3:1");
+}
+
+#[test]
+fn _0011() {
+  // Markers inside a closed multiline key are not reported.
+  eq("\"key\"key\"\nvalue\n", 1, "");
+}
+
+#[test]
+fn _0012() {
+  // Duplicated keys.
+  eq("a\nb\nc\nd\na\ne\n\"c\"\nf\n", 2, "warning[duplicate-key]:5:1-5:2,warning[duplicate-key]:7:1-7:4");
+}
+
+#[test]
+fn _0013() {
+  // Diagnostic message.
+  let (_, diagnostics) = load_from_string_with_diagnostics("a\n\n\"b", &['"']);
+  assert_eq!(Severity::Error, diagnostics[0].severity());
+  assert_eq!("error[unterminated-value]: unterminated multiline value at line 3, column 1", diagnostics[0].to_string());
+}
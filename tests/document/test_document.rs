@@ -0,0 +1,164 @@
+use super::*;
+use kivi::{load_from_string, Document, DuplicatePolicy, ErrorKind, LoaderOptions, TrimPolicy};
+
+#[test]
+fn _0001() {
+  // Unchanged documents are written back byte-for-byte.
+  let options = LoaderOptions::new().markers(&['"', '@', '~', '^']);
+  for input in [DATA_PROPERTIES, DATA_ISSUES, DATA_001, DATA_002, DATA_003, DATA_004, DATA_005, DATA_006, DATA_TASKS] {
+    assert_eq!(input, options.load_document(input).unwrap().to_string());
+    let crlf = input.replace('\n', "\r\n");
+    assert_eq!(crlf, options.load_document(&crlf).unwrap().to_string());
+  }
+}
+
+#[test]
+fn _0002() {
+  // Malformed input, indentation and missing final line ending are retained.
+  for input in ["", "\n\n", "  a  \n\t b \n", "a\nb", "a\nb\nc", "\"a\nb\"  \n  c\n\n\nd\n\"e", "a\r\nb\rc\nd"] {
+    assert_eq!(input, Document::parse(input).to_string());
+  }
+}
+
+#[test]
+fn _0003() {
+  // Changing a value modifies only its source text.
+  let mut document = Document::parse("\n\nhost\n  127.0.0.1  \n\nport\n54321\n");
+  assert!(document.set("port", "8080").unwrap());
+  assert_eq!("\n\nhost\n  127.0.0.1  \n\nport\n8080\n", document.to_string());
+}
+
+#[test]
+fn _0004() {
+  // Multiline values keep their marker and the document line ending.
+  let options = LoaderOptions::new().markers(&['"', '@']);
+  let mut document = options.load_document("a\r\n@b\r\nc@\r\nd\r\ne\r\n").unwrap();
+  assert!(document.set("a", "x\n\"y\"").unwrap());
+  assert!(document.set("d", "z\nw").unwrap());
+  assert_eq!("a\r\n@x\r\n\"y\"@\r\nd\r\n\"z\r\nw\"\r\n", document.to_string());
+  assert_eq!(options.load_str(&document.to_string()).unwrap(), document.to_key_value_pairs());
+}
+
+#[test]
+fn _0005() {
+  // The marker is changed when the value can not be enclosed in it.
+  let mut document = LoaderOptions::new().markers(&['"', '@']).load_document("\"a\"\n\"b\nc\"\n").unwrap();
+  assert!(document.set("a", "d\"\ne").unwrap());
+  assert_eq!("\"a\"\n@d\"\ne@\n", document.to_string());
+  assert_eq!(ErrorKind::Unrepresentable, *document.set("a", "\"\n@\n").unwrap_err().kind());
+  assert_eq!("\"a\"\n@d\"\ne@\n", document.to_string());
+}
+
+#[test]
+fn _0006() {
+  // Line ending is added after a new multiline value.
+  let mut document = Document::parse("a\n  b  \nc\nd\n");
+  assert!(document.set("a", "d\ne").unwrap());
+  assert_eq!("a\n  \"d\ne\"\nc\nd\n", document.to_string());
+  let mut document = Document::parse("a\nb");
+  assert!(document.set("a", "d\ne").unwrap());
+  assert_eq!("a\n\"d\ne\"\n", document.to_string());
+}
+
+#[test]
+fn _0007() {
+  // Inserted key-value pairs are appended using the document line ending.
+  let mut document = Document::parse("a\r\nb");
+  document.insert("c", "d").unwrap();
+  document.insert("e\nf", "g").unwrap();
+  document.insert("a", "h").unwrap();
+  assert_eq!("a\r\nh\r\nc\r\nd\r\n\"e\r\nf\"\r\ng\r\n", document.to_string());
+  let mut document = Document::parse("");
+  document.insert("a", "b").unwrap();
+  assert_eq!("a\nb\n", document.to_string());
+}
+
+#[test]
+fn _0008() {
+  // Removing a key removes all its occurrences with their lines.
+  let mut document = LoaderOptions::new()
+    .duplicates(DuplicatePolicy::KeepAll)
+    .load_document("a\nb\n\n  c\n  d\n\ne\nf\nc\ng")
+    .unwrap();
+  assert_eq!(4, document.len());
+  assert!(document.remove("c"));
+  assert_eq!("a\nb\n\n\ne\nf\n", document.to_string());
+  assert!(document.remove("a"));
+  assert!(document.remove("e"));
+  assert!(document.is_empty());
+  assert_eq!("\n\n", document.to_string());
+}
+
+#[test]
+fn _0009() {
+  // Values are resolved according to the policy for duplicated keys.
+  let input = "a\nb\na\nc\n";
+  assert_eq!("c", Document::parse(input).get("a").unwrap());
+  let mut document = LoaderOptions::new().duplicates(DuplicatePolicy::FirstWins).load_document(input).unwrap();
+  assert_eq!("b", document.get("a").unwrap());
+  document.set("a", "d").unwrap();
+  assert_eq!("a\nd\na\nc\n", document.to_string());
+  assert_eq!(
+    "a:d,a:c",
+    document.key_value_pairs().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<String>>().join(",")
+  );
+}
+
+#[test]
+fn _0010() {
+  // Key-value pairs of the document are the same as loaded, spans are kept for unchanged values.
+  for input in [DATA_PROPERTIES, DATA_002, DATA_005, DATA_TASKS] {
+    let mut document = Document::parse(input);
+    assert_eq!(load_from_string(input), document.to_key_value_pairs());
+    let key = document.key_value_pairs().next().unwrap().0.to_string();
+    document.set(&key, "changed").unwrap();
+    let kvp = document.to_key_value_pairs();
+    assert_eq!(None, kvp.span_of_value(&key));
+    assert!(kvp.span_of_key(&key).is_some());
+    assert_eq!(load_from_string(&document.to_string()), kvp);
+  }
+}
+
+#[test]
+fn _0011() {
+  // Strict mode reports malformed input.
+  assert_eq!(ErrorKind::MissingValue, *LoaderOptions::new().strict(true).load_document("a\nb\nc\n").unwrap_err().kind());
+  let (document, diagnostics) = LoaderOptions::new().load_document_with_diagnostics("a\nb\nc\n");
+  assert_eq!(1, document.len());
+  assert_eq!("missing-value", diagnostics[0].code());
+}
+
+#[test]
+fn _0012() {
+  // Key-value pairs are not appended after a key without value or an unterminated multiline key or value.
+  for (input, kind, line) in [
+    ("a\nb\nc\n", ErrorKind::MissingValue, 3),
+    ("a\nb\nc", ErrorKind::MissingValue, 3),
+    ("a\nb\n\"c\n", ErrorKind::UnterminatedKey, 3),
+    ("a\nb\nc\n\"d\n", ErrorKind::UnterminatedValue, 4),
+  ] {
+    let mut document = Document::parse(input);
+    let err = document.insert("d", "e").unwrap_err();
+    assert_eq!((&kind, line), (err.kind(), err.line()));
+    assert_eq!(input, document.to_string());
+    assert!(document.set("a", "f").unwrap());
+    assert!(document.remove("a"));
+    assert!(document.insert("d", "e").is_err());
+  }
+  let mut document = Document::parse("a\nb\n\"c\nd\"\ne\n\n");
+  document.insert("f", "g").unwrap();
+  assert_eq!("g", load_from_string(&document.to_string()).get("f").unwrap());
+}
+
+#[test]
+fn _0013() {
+  // Preserved whitespaces around the empty value token are replaced together with the token.
+  let options = LoaderOptions::new().empty_value("<e>").trim(TrimPolicy::Preserve);
+  for input in ["k\n  <e>\n", "k\n<e>  \n", "k\n \t<e> \n"] {
+    let mut document = options.load_document(input).unwrap();
+    assert_eq!("", document.get("k").unwrap());
+    assert!(document.set("k", "x").unwrap());
+    assert_eq!("k\nx\n", document.to_string());
+    assert_eq!("x", options.load_str(&document.to_string()).unwrap().get("k").unwrap());
+  }
+}
@@ -1,5 +1,9 @@
+mod test_duplicate_policy;
 mod test_load_from_file;
+mod test_load_from_reader;
 mod test_load_from_string;
+mod test_try_load_from_file;
+mod test_try_load_from_string;
 
 use super::*;
 use kivi::{load_from_string, load_from_string_markers};
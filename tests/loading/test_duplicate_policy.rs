@@ -0,0 +1,74 @@
+use kivi::{load_from_string_markers, load_from_string_with_diagnostics, DuplicatePolicy, ErrorKind, KeyValuePairs, LoaderOptions};
+
+const INPUT: &str = "a\nb\nc\nd\na\ne\nc\nf\na\ng\n";
+
+fn ordered(kvp: &KeyValuePairs) -> String {
+  kvp.ordered_key_value_pairs().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<String>>().join(",")
+}
+
+#[test]
+fn default_policy_should_be_last_wins() {
+  let kvp = load_from_string_markers(INPUT, &['"']);
+  assert_eq!(kvp, LoaderOptions::new().duplicates(DuplicatePolicy::LastWins).load_str(INPUT).unwrap());
+  assert_eq!(DuplicatePolicy::LastWins, DuplicatePolicy::default());
+}
+
+#[test]
+fn first_wins_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::FirstWins).load_str(INPUT).unwrap();
+  assert_eq!(2, kvp.len());
+  assert_eq!(2, kvp.keys().count());
+  assert_eq!(2, kvp.ordered_keys().count());
+  assert_eq!("b", kvp.get("a").unwrap());
+  assert_eq!("d", kvp.get("c").unwrap());
+  assert_eq!("a:b,c:d", ordered(&kvp));
+}
+
+#[test]
+fn last_wins_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::LastWins).load_str(INPUT).unwrap();
+  assert_eq!(2, kvp.len());
+  assert_eq!(2, kvp.keys().count());
+  assert_eq!(2, kvp.ordered_keys().count());
+  assert_eq!("g", kvp.get("a").unwrap());
+  assert_eq!("f", kvp.get("c").unwrap());
+  assert_eq!("a:g,c:f", ordered(&kvp));
+}
+
+#[test]
+fn reject_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::Reject).load_str(INPUT).unwrap();
+  assert_eq!(2, kvp.len());
+  assert_eq!("a:b,c:d", ordered(&kvp));
+  let error = LoaderOptions::new().duplicates(DuplicatePolicy::Reject).strict(true).load_str(INPUT).unwrap_err();
+  assert_eq!(&ErrorKind::DuplicateKey, error.kind());
+  assert_eq!(5, error.line());
+  assert_eq!(1, error.column());
+}
+
+#[test]
+fn keep_all_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(INPUT).unwrap();
+  assert_eq!(5, kvp.len());
+  assert_eq!(5, kvp.ordered_keys().count());
+  assert_eq!(5, kvp.ordered_values().count());
+  assert_eq!("g", kvp.get("a").unwrap());
+  assert_eq!("f", kvp.get("c").unwrap());
+  assert_eq!("a:b,c:d,a:e,c:f,a:g", ordered(&kvp));
+}
+
+#[test]
+fn policies_should_not_fail_without_duplicates() {
+  for policy in [DuplicatePolicy::FirstWins, DuplicatePolicy::LastWins, DuplicatePolicy::Reject, DuplicatePolicy::KeepAll] {
+    let kvp = LoaderOptions::new().duplicates(policy).strict(true).load_str("a\nb\nc\nd\n").unwrap();
+    assert_eq!("a:b,c:d", ordered(&kvp));
+  }
+}
+
+#[test]
+fn duplicates_should_be_reported_as_warnings() {
+  let (kvp, diagnostics) = load_from_string_with_diagnostics(INPUT, &['"']);
+  assert_eq!(2, kvp.len());
+  assert_eq!(3, diagnostics.len());
+  assert!(diagnostics.iter().all(|d| d.code() == "duplicate-key"));
+}
@@ -0,0 +1,92 @@
+use super::*;
+use kivi::{load_from_reader, load_from_string, ErrorKind, LoaderOptions};
+use std::io;
+use std::io::{BufReader, Read};
+
+/// Reader returning an error after reading all bytes.
+struct FailingReader<'a>(&'a [u8]);
+
+impl Read for FailingReader<'_> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if self.0.is_empty() {
+      return Err(io::Error::other("connection reset"));
+    }
+    let n = self.0.read(buf)?;
+    Ok(n)
+  }
+}
+
+#[test]
+fn loading_from_reader_should_work() {
+  for input in [DATA_001, DATA_002, DATA_003, DATA_004, DATA_005, DATA_006, DATA_PROPERTIES, DATA_ISSUES, DATA_TASKS] {
+    assert_eq!(load_from_string(input), load_from_reader(input.as_bytes()).unwrap());
+  }
+}
+
+#[test]
+fn loading_from_reader_with_single_byte_buffer_should_work() {
+  for input in [
+    DATA_001,
+    DATA_002,
+    DATA_006,
+    DATA_PROPERTIES,
+    "a\r\nb\r\n\"c\r\nd\"\r\n\"e\rf\"\r",
+    "źdźbło\r\n\"żółć\r\n🦀\"\r\n",
+  ] {
+    let kvp = load_from_reader(BufReader::with_capacity(1, input.as_bytes())).unwrap();
+    assert_eq!(load_from_string(input), kvp);
+    for key in kvp.keys() {
+      assert_eq!(load_from_string(input).span_of_value(key), kvp.span_of_value(key));
+    }
+  }
+}
+
+#[test]
+fn loading_line_endings_split_between_chunks_should_work() {
+  let input = "\"a\r\nb\"\r\n\"c\r\nd\"\r\n";
+  for capacity in 1..input.len() {
+    let kvp = load_from_reader(BufReader::with_capacity(capacity, input.as_bytes())).unwrap();
+    assert_eq!("c\nd", kvp.get("a\nb").unwrap());
+  }
+}
+
+#[test]
+fn loading_invalid_utf8_should_fail() {
+  for bytes in [&b"a\nb\xC3\x28\n"[..], &b"a\nb\xF0\x9F\xA6"[..], &b"a\n\xFF"[..]] {
+    let error = load_from_reader(bytes).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, error.kind());
+  }
+}
+
+#[test]
+fn loading_from_failing_reader_should_fail() {
+  let error = load_from_reader(BufReader::new(FailingReader(b"a\nb\n"))).unwrap_err();
+  assert_eq!("connection reset", error.to_string());
+  let error = LoaderOptions::new().load_reader(BufReader::new(FailingReader(b"a\nb\n"))).unwrap_err();
+  assert_eq!(&ErrorKind::Io(io::ErrorKind::Other), error.kind());
+  assert_eq!("I/O error: connection reset", error.to_string());
+  let source = std::error::Error::source(&error).unwrap().downcast_ref::<io::Error>().unwrap();
+  assert_eq!("connection reset", source.to_string());
+}
+
+#[test]
+fn loading_from_reader_with_options_should_work() {
+  let (kvp, diagnostics) = LoaderOptions::new().load_reader_with_diagnostics("a\nb\nc\n".as_bytes()).unwrap();
+  assert_eq!(1, kvp.len());
+  assert_eq!("missing-value", diagnostics[0].code());
+  let error = LoaderOptions::new().strict(true).load_reader("a\nb\nc\n".as_bytes()).unwrap_err();
+  assert_eq!(&ErrorKind::MissingValue, error.kind());
+}
+
+#[test]
+fn loading_large_input_from_reader_should_work() {
+  let reader = "key\nvalue\n"
+    .as_bytes()
+    .chain(io::repeat(b' ').take(1_000_000))
+    .chain("\nlast\n\"multiline\nvalue\"".as_bytes());
+  let kvp = load_from_reader(BufReader::new(reader)).unwrap();
+  assert_eq!(1, kvp.len());
+  let (kvp, diagnostics) = LoaderOptions::new().load_reader_with_diagnostics(BufReader::new(io::repeat(b'a').take(1_000_000))).unwrap();
+  assert!(kvp.is_empty());
+  assert_eq!("missing-value", diagnostics[0].code());
+}
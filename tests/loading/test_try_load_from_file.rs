@@ -0,0 +1,39 @@
+use super::*;
+use kivi::{try_load_from_file, try_load_from_file_markers, ErrorKind, LoaderOptions};
+use std::error::Error;
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+#[test]
+fn loading_from_properties_file_should_work() {
+  let kvp = try_load_from_file(FILE_PROPERTIES).unwrap();
+  assert_eq!(2, kvp.len());
+  assert_eq!("127.0.0.1", kvp.get("host").unwrap());
+  assert_eq!("Multiline\n description", kvp.get("description").unwrap());
+}
+
+#[test]
+fn loading_from_issues_file_should_work() {
+  let kvp = try_load_from_file_markers(FILE_ISSUES, &['@', '~', '^']).unwrap();
+  assert_eq!(2, kvp.len());
+  assert_eq!("Build a separate\n server", kvp.get("Issue1").unwrap());
+  assert_eq!("Develop a new\n compiler", kvp.get("Issue2").unwrap());
+}
+
+#[test]
+fn loading_from_non_existing_file_should_fail() {
+  let error = try_load_from_file("tests/data/non-existing.kivi").unwrap_err();
+  assert_eq!(&ErrorKind::Io(io::ErrorKind::NotFound), error.kind());
+  assert_eq!(io::ErrorKind::NotFound, error.source().unwrap().downcast_ref::<io::Error>().unwrap().kind());
+  assert_eq!(0, error.line());
+  assert_eq!(0, error.column());
+}
+
+#[test]
+fn loading_results_should_be_unwind_safe() {
+  fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe + Send + Sync>(_: &T) {}
+  assert_unwind_safe(&try_load_from_file("tests/data/non-existing.kivi"));
+  assert_unwind_safe(&LoaderOptions::new().load_document("a\nb\n"));
+  let error = try_load_from_file("tests/data/non-existing.kivi").unwrap_err();
+  assert_eq!(error.to_string(), std::panic::catch_unwind(|| error.to_string()).unwrap());
+}
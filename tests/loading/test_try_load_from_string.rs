@@ -0,0 +1,102 @@
+use super::*;
+use kivi::{try_load_from_string, try_load_from_string_markers, ErrorKind};
+
+fn err(input: &str, kind: ErrorKind, line: usize, column: usize) {
+  let error = try_load_from_string(input).unwrap_err();
+  assert_eq!(&kind, error.kind());
+  assert_eq!(line, error.line());
+  assert_eq!(column, error.column());
+}
+
+#[test]
+fn _0001() {
+  // Input string is empty.
+  assert!(try_load_from_string("").unwrap().is_empty());
+}
+
+#[test]
+fn _0002() {
+  // Input string contains only whitespaces and newlines.
+  assert!(try_load_from_string("   \n \t \r\n   \r   ").unwrap().is_empty());
+}
+
+#[test]
+fn _0003() {
+  // Key and value pair.
+  let kvp = try_load_from_string("a\nb\n").unwrap();
+  assert_eq!("b", kvp.get("a").unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Properties file.
+  let kvp = try_load_from_string(DATA_PROPERTIES).unwrap();
+  assert_eq!(2, kvp.len());
+  assert_eq!("Multiline\n description", kvp.get("description").unwrap());
+}
+
+#[test]
+fn _0005() {
+  // Single key without value, no newline.
+  err("a", ErrorKind::MissingValue, 1, 1);
+}
+
+#[test]
+fn _0006() {
+  // Single key without value, followed by empty lines.
+  err("\n   a\n      \n", ErrorKind::MissingValue, 2, 4);
+}
+
+#[test]
+fn _0007() {
+  // Multiline key without value.
+  err("a\nb\n\n  \"c\n d\"\n", ErrorKind::MissingValue, 4, 3);
+}
+
+#[test]
+fn _0008() {
+  // Multiline key is not closed.
+  err("\"key", ErrorKind::UnterminatedKey, 1, 1);
+}
+
+#[test]
+fn _0009() {
+  // Multiline value is not closed.
+  err("\"key\"\n\"value", ErrorKind::UnterminatedValue, 2, 1);
+}
+
+#[test]
+fn _0010() {
+  // Multiline key and non multiline value in one line.
+  err("\"key\"  value", ErrorKind::UnterminatedKey, 1, 1);
+}
+
+#[test]
+fn _0011() {
+  // Multiline key with multiline value not ended with newline after marker.
+  err("\"key\"\n \"value\" \r", ErrorKind::UnterminatedValue, 2, 2);
+}
+
+#[test]
+fn _0012() {
+  // Lines counted for all kinds of line endings.
+  err("a\r\nb\rc\nd\n\r\n  x\n  \"y", ErrorKind::UnterminatedValue, 7, 3);
+}
+
+#[test]
+fn _0013() {
+  // Custom markers.
+  let error = try_load_from_string_markers("a\nb\n  `c\nd", &['`']).unwrap_err();
+  assert_eq!(&ErrorKind::UnterminatedKey, error.kind());
+  assert_eq!(3, error.line());
+  assert_eq!(3, error.column());
+  let kvp = try_load_from_string_markers("`key`key`\n`value`value`\n", &['`']).unwrap();
+  assert_eq!("value`value", kvp.get("key`key").unwrap());
+}
+
+#[test]
+fn _0014() {
+  // Error message.
+  let error = try_load_from_string("a\n\n\"b").unwrap_err();
+  assert_eq!("unterminated multiline value at line 3, column 1", error.to_string());
+}
@@ -1,6 +1,11 @@
 mod api;
 mod cloning;
+mod diagnostics;
+mod document;
 mod loading;
+mod options;
+mod parsing;
+mod writing;
 
 const FILE_PROPERTIES: &str = "tests/data/properties.kivi";
 const DATA_PROPERTIES: &str = include_str!("data/properties.kivi");
@@ -18,3 +23,4 @@ const FILE_005: &str = "tests/data/data005.kivi";
 const DATA_005: &str = include_str!("data/data005.kivi");
 const FILE_006: &str = "tests/data/data006.kivi";
 const DATA_006: &str = include_str!("data/data006.kivi");
+const DATA_TASKS: &str = include_str!("data/tasks.kivi");
@@ -0,0 +1,11 @@
+use super::*;
+
+mod test_comments;
+mod test_dedent;
+mod test_delimiters;
+mod test_escapes;
+mod test_heredoc;
+mod test_line_endings;
+mod test_loader_options;
+mod test_strict_markers;
+mod test_trim;
@@ -0,0 +1,117 @@
+use super::*;
+use kivi::{load_from_string, Document, DuplicatePolicy, LoaderOptions};
+
+fn comments(kvp: &kivi::KeyValuePairs, key: &str) -> String {
+  kvp.comments(key).collect::<Vec<&str>>().join("|")
+}
+
+#[test]
+fn _0001() {
+  // Comments are disabled by default.
+  let kvp = load_from_string("# a\nb\n");
+  assert_eq!("b", kvp.get("# a").unwrap());
+  assert_eq!("", comments(&kvp, "# a"));
+  for input in [DATA_PROPERTIES, DATA_002, DATA_TASKS] {
+    assert_eq!(load_from_string(input), LoaderOptions::new().comments(&["//"]).load_str(input).unwrap());
+  }
+}
+
+#[test]
+fn _0002() {
+  // Comment lines are skipped.
+  let input = "# header\n\n# a\na\nb\n  // c\n\"c\"\n// d\n\"d\"\n#\n";
+  let kvp = LoaderOptions::new().comments(&["#", "//"]).strict(true).load_str(input).unwrap();
+  assert_eq!(
+    "a:b,c:d",
+    kvp.ordered_key_value_pairs().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<String>>().join(",")
+  );
+  assert_eq!("a", comments(&kvp, "a"));
+  assert_eq!("c|d", comments(&kvp, "c"));
+  assert_eq!("", comments(&kvp, "x"));
+}
+
+#[test]
+fn _0003() {
+  // Lines inside multiline keys and values are content.
+  let kvp = LoaderOptions::new().comments(&["#"]).load_str("\"a\n# b\"\n\"\n# c\n\"\n").unwrap();
+  assert_eq!("\n# c\n", kvp.get("a\n# b").unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Comment prefix is recognized only at the start of a line.
+  let kvp = LoaderOptions::new().comments(&["#"]).load_str("a#b\nc # d\n").unwrap();
+  assert_eq!("c # d", kvp.get("a#b").unwrap());
+}
+
+#[test]
+fn _0005() {
+  // Comments are attached to the occurrence providing the value.
+  let input = "# first\na\nb\n# second\na\nc\n";
+  let kvp = LoaderOptions::new().comments(&["#"]).load_str(input).unwrap();
+  assert_eq!("second", comments(&kvp, "a"));
+  let kvp = LoaderOptions::new().comments(&["#"]).duplicates(DuplicatePolicy::FirstWins).load_str(input).unwrap();
+  assert_eq!("first", comments(&kvp, "a"));
+}
+
+#[test]
+fn _0006() {
+  // Comments are available from borrowed key-value pairs and documents.
+  let options = LoaderOptions::new().comments(&["#"]);
+  let input = "# Host name\nhost\n127.0.0.1\n";
+  let kvp = options.load_str_ref(input).unwrap();
+  assert_eq!(vec!["Host name"], kvp.comments("host").collect::<Vec<&str>>());
+  assert_eq!("Host name", comments(&kvp.into_owned(), "host"));
+  let document = options.load_document(input).unwrap();
+  assert_eq!(vec!["Host name"], document.comments("host").collect::<Vec<&str>>());
+  assert_eq!(input, document.to_string());
+  assert_eq!(options.load_str(input).unwrap(), document.to_key_value_pairs());
+  assert_eq!("Host name", comments(&document.to_key_value_pairs(), "host"));
+  assert_eq!(0, Document::parse(input).comments("host").count());
+}
+
+#[test]
+fn _0007() {
+  // Comments are recognized when loading from reader, comments between the key and the value are attached to the key.
+  let input = "// a\nb\n// c\nd\n";
+  let options = LoaderOptions::new().comments(&["//"]);
+  assert_eq!(options.load_str(input).unwrap(), options.load_reader(input.as_bytes()).unwrap());
+  assert_eq!("a|c", comments(&options.load_reader(input.as_bytes()).unwrap(), "b"));
+}
+
+#[test]
+fn _0008() {
+  // Changed keys and values starting with a comment prefix are enclosed in delimiters.
+  let options = LoaderOptions::new().comments(&["#", "//"]);
+  let mut document = options.load_document("a\nb\n").unwrap();
+  document.set("a", "# x").unwrap();
+  document.insert("  // y", "z").unwrap();
+  document.insert("c", "d # e").unwrap();
+  assert_eq!("a\n\"# x\"\n\"  // y\"\nz\nc\nd # e\n", document.to_string());
+  let kvp = options.load_str(&document.to_string()).unwrap();
+  assert_eq!("# x", kvp.get("a").unwrap());
+  assert_eq!("z", kvp.get("  // y").unwrap());
+  assert_eq!("d # e", kvp.get("c").unwrap());
+  let mut document = Document::parse("a\nb\n");
+  document.set("a", "# x").unwrap();
+  assert_eq!("a\n# x\n", document.to_string());
+}
+
+#[test]
+fn _0009() {
+  // Removed keys take their attached comment lines with them.
+  let options = LoaderOptions::new().comments(&["#"]);
+  let mut document = options.load_document("# about a\na\n1\nb\n2\n").unwrap();
+  assert!(document.remove("a"));
+  assert_eq!("b\n2\n", document.to_string());
+  assert_eq!(0, document.comments("b").count());
+  let input = "# header\n\n  # about a\n  # more\n  a\n  1\n# about b\nb\n# inside\n2\n\n# trailing\n";
+  let mut document = options.load_document(input).unwrap();
+  assert!(document.remove("a"));
+  assert_eq!("# header\n\n# about b\nb\n# inside\n2\n\n# trailing\n", document.to_string());
+  assert_eq!(vec!["about b", "inside"], document.comments("b").collect::<Vec<&str>>());
+  assert!(document.remove("b"));
+  assert_eq!("# header\n\n\n# trailing\n", document.to_string());
+  let kvp = options.load_str(&document.to_string()).unwrap();
+  assert!(kvp.is_empty());
+}
@@ -0,0 +1,103 @@
+use super::*;
+use kivi::{load_from_string, Dedent, ErrorKind, LoaderOptions};
+
+#[test]
+fn _0001() {
+  // Indentation and blank lines are kept by default.
+  let kvp = load_from_string(DATA_PROPERTIES);
+  assert_eq!("Multiline\n description", kvp.get("description").unwrap());
+  let options = LoaderOptions::new().dedent(Dedent::None).trim_first_blank_line(false).trim_last_blank_line(false);
+  assert_eq!(kvp, options.load_str(DATA_PROPERTIES).unwrap());
+}
+
+#[test]
+fn _0002() {
+  // Continuation lines aligned with the text following the opening delimiter lose their indentation.
+  for dedent in [Dedent::Common, Dedent::Marker] {
+    let kvp = LoaderOptions::new().dedent(dedent).load_str(DATA_PROPERTIES).unwrap();
+    assert_eq!("Multiline\ndescription", kvp.get("description").unwrap());
+    assert_eq!("127.0.0.1", kvp.get("host").unwrap());
+  }
+}
+
+#[test]
+fn _0003() {
+  // Common indentation of non-blank continuation lines is stripped, blank lines are preserved.
+  let options = LoaderOptions::new().dedent(Dedent::Common);
+  let kvp = options.load_str("a\n\"b\n    c\n\n      d\n   \n    e\"\n").unwrap();
+  assert_eq!("b\nc\n\n  d\n\ne", kvp.get("a").unwrap());
+  let kvp = options.load_str("a\n\"b\n\tc\n  d\"\n").unwrap();
+  assert_eq!("b\nc\n d", kvp.get("a").unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Indentation is stripped up to the column of the text following the opening delimiter.
+  let options = LoaderOptions::new().dedent(Dedent::Marker);
+  let kvp = options.load_str("  a\n  \"b\n     c\n d\n   e\"\n").unwrap();
+  assert_eq!("b\n  c\nd\ne", kvp.get("a").unwrap());
+  let options = LoaderOptions::new().dedent(Dedent::Marker).delimiters(&[("<<<", ">>>")]);
+  let kvp = options.load_str("<<<a\n   b>>>\n<<<c\n    d>>>\n").unwrap();
+  assert_eq!("c\n d", kvp.get("a\nb").unwrap());
+}
+
+#[test]
+fn _0005() {
+  // Blank first and last lines allow placing delimiters in separate lines.
+  let input = "  template\n  \"\n    Hello,\n\n      welcome!\n  \"\n";
+  let options = LoaderOptions::new().dedent(Dedent::Common).trim_first_blank_line(true).trim_last_blank_line(true);
+  let kvp = options.load_str(input).unwrap();
+  assert_eq!("Hello,\n\n  welcome!", kvp.get("template").unwrap());
+  let kvp = LoaderOptions::new().trim_first_blank_line(true).load_str(input).unwrap();
+  assert_eq!("    Hello,\n\n      welcome!\n  ", kvp.get("template").unwrap());
+  let kvp = LoaderOptions::new().trim_last_blank_line(true).load_str(input).unwrap();
+  assert_eq!("\n    Hello,\n\n      welcome!", kvp.get("template").unwrap());
+  // Single-line texts and non-blank lines are never trimmed.
+  let kvp = options.load_str("a\n\"  \"\nb\n\"c\nd\"\n").unwrap();
+  assert_eq!("  ", kvp.get("a").unwrap());
+  assert_eq!("c\nd", kvp.get("b").unwrap());
+}
+
+#[test]
+fn _0006() {
+  // All lines of heredocs are stripped.
+  let input = "script\n  <<END\n  if true; then\n    echo \"ok\"\n  fi\nEND\n";
+  for dedent in [Dedent::Common, Dedent::Marker] {
+    let kvp = LoaderOptions::new().heredoc(true).dedent(dedent).load_str(input).unwrap();
+    assert_eq!("if true; then\n  echo \"ok\"\nfi", kvp.get("script").unwrap());
+  }
+}
+
+#[test]
+fn _0007() {
+  // Loading from reader gives the same result as loading from string.
+  let input = "  a\r\n  \"\r\n    b\r\n      c\r\n  \"\r\n\"d\n e\"\nf\n";
+  let options = LoaderOptions::new().dedent(Dedent::Common).trim_first_blank_line(true).trim_last_blank_line(true);
+  let kvp = options.load_str(input).unwrap();
+  assert_eq!("b\n  c", kvp.get("a").unwrap());
+  assert_eq!("f", kvp.get("d\ne").unwrap());
+  assert_eq!(kvp, options.load_reader(input.as_bytes()).unwrap());
+}
+
+#[test]
+fn _0008() {
+  // Changed multiline keys and values are written so they are loaded back unchanged.
+  let options = LoaderOptions::new().dedent(Dedent::Common);
+  let mut document = options.load_document("k\nv\n").unwrap();
+  assert_eq!(&ErrorKind::Unrepresentable, document.set("k", "a\n  b\n  c").unwrap_err().kind());
+  assert_eq!("k\nv\n", document.to_string());
+  document.set("k", "a\nb\n  c").unwrap();
+  assert_eq!("a\nb\n  c", options.load_str(&document.to_string()).unwrap().get("k").unwrap());
+  let options = LoaderOptions::new().dedent(Dedent::Marker).heredoc(true);
+  let mut document = options.load_document("  k\n  v\nh\n  <<END\n  x\nEND\n").unwrap();
+  document.set("k", "a\n  b\n\nc").unwrap();
+  document.set("h", "  y\nz").unwrap();
+  document.insert("\n d", "e").unwrap();
+  assert_eq!("  k\n  \"a\n     b\n\n   c\"\nh\n  <<END\n    y\n  z\nEND\n\"\n  d\"\ne\n", document.to_string());
+  assert_eq!(document.to_key_value_pairs(), options.load_str(&document.to_string()).unwrap());
+  let options = LoaderOptions::new().trim_first_blank_line(true).trim_last_blank_line(true);
+  let mut document = options.load_document("k\nv\n").unwrap();
+  document.set("k", " \nx\n ").unwrap();
+  assert_eq!("k\n\"\n \nx\n \n\"\n", document.to_string());
+  assert_eq!(" \nx\n ", options.load_str(&document.to_string()).unwrap().get("k").unwrap());
+}
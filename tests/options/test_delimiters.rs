@@ -0,0 +1,81 @@
+use super::*;
+use kivi::{load_from_string, Document, ErrorKind, KeyValuePairs, LoaderOptions, WriterOptions};
+
+#[test]
+fn _0001() {
+  // Markers are delimiters with the same opening and closing character.
+  let options = LoaderOptions::new().delimiters(&[("@", "@"), ("~", "~"), ("^", "^")]);
+  assert_eq!(
+    LoaderOptions::new().markers(&['@', '~', '^']).load_str(DATA_ISSUES).unwrap(),
+    options.load_str(DATA_ISSUES).unwrap()
+  );
+  assert_eq!(
+    load_from_string(DATA_PROPERTIES),
+    LoaderOptions::new().delimiters(&[("\"", "\"")]).load_str(DATA_PROPERTIES).unwrap()
+  );
+}
+
+#[test]
+fn _0002() {
+  // Asymmetric delimiters.
+  let options = LoaderOptions::new().delimiters(&[("[", "]"), ("{", "}"), ("«", "»")]).strict(true);
+  let kvp = options.load_str("[a\n[b]\n{c{\n}\n«d\n»\n[e] f]\n").unwrap();
+  assert_eq!("c{\n", kvp.get("a\n[b").unwrap());
+  assert_eq!("e] f", kvp.get("d\n").unwrap());
+}
+
+#[test]
+fn _0003() {
+  // Multi-character delimiters.
+  let options = LoaderOptions::new().delimiters(&[("<<<", ">>>")]).strict(true);
+  let kvp = options.load_str("a<<\n<<<b\n>>\n>>>\nc\n  <<<>>>\n").unwrap();
+  assert_eq!("b\n>>\n", kvp.get("a<<").unwrap());
+  assert_eq!("", kvp.get("c").unwrap());
+  assert_eq!(kvp, options.load_reader("a<<\n<<<b\n>>\n>>>\nc\n  <<<>>>\n".as_bytes()).unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Invalid delimiters are skipped.
+  let options = LoaderOptions::new().delimiters(&[("", "]"), ("[", ""), ("(\n", ")"), ("{", "}")]);
+  let kvp = options.load_str("[a]\n(\nb)\n{c\n}\n").unwrap();
+  assert_eq!("(", kvp.get("[a]").unwrap());
+  assert_eq!(None, kvp.get("c"));
+}
+
+#[test]
+fn _0005() {
+  // Separate delimiters for keys and values.
+  let options = LoaderOptions::new().key_delimiters(&[("<", ">")]).value_delimiters(&[("[", "]")]);
+  let kvp = options.load_str("<a\n>\n[b\n]\n[c]\n<d>\n").unwrap();
+  assert_eq!("b\n", kvp.get("a\n").unwrap());
+  assert_eq!("<d>", kvp.get("[c]").unwrap());
+}
+
+#[test]
+fn _0006() {
+  // Writing with delimiters keeps texts unchanged.
+  let options = WriterOptions::new().delimiters(&[("[", "]"), ("<<<", ">>>")]);
+  let mut kvp = KeyValuePairs::new();
+  kvp.insert("a[b", "c]\nd");
+  kvp.insert("e\n", "]\n>>>");
+  kvp.insert("f", "g]");
+  let output = options.format(&kvp).unwrap();
+  assert_eq!("[a[b]\n<<<c]\nd>>>\n[e\n]\n<<<]\n>>>>>>\nf\ng]\n", output);
+  assert_eq!(kvp, LoaderOptions::new().delimiters(&[("[", "]"), ("<<<", ">>>")]).load_str(&output).unwrap());
+  kvp.insert("h", "]\n>>>\n");
+  assert_eq!(ErrorKind::Unrepresentable, *options.format(&kvp).unwrap_err().kind());
+}
+
+#[test]
+fn _0007() {
+  // Documents keep delimiters of changed values.
+  let options = LoaderOptions::new().delimiters(&[("\"", "\""), ("<<<", ">>>")]);
+  let mut document = options.load_document("a\n<<<b\nc>>>\n").unwrap();
+  document.set("a", "d\n\"e\"\n").unwrap();
+  assert_eq!("a\n<<<d\n\"e\"\n>>>\n", document.to_string());
+  document.insert("f", "g\nh").unwrap();
+  assert_eq!("a\n<<<d\n\"e\"\n>>>\nf\n\"g\nh\"\n", document.to_string());
+  assert_eq!(options.load_str(&document.to_string()).unwrap(), document.to_key_value_pairs());
+  assert_eq!(1, Document::parse("[a]\nb\n").len());
+}
@@ -0,0 +1,102 @@
+use super::*;
+use kivi::{load_from_string, ErrorKind, Event, KeyValuePairs, LoaderOptions, Severity, WriterOptions};
+
+#[test]
+fn _0001() {
+  // Escape sequences are disabled by default.
+  let input = "\" \\a \"\n\" \\b \"\n\"c\\\"\nd\n";
+  let kvp = load_from_string(input);
+  assert_eq!(" \\b ", kvp.get(" \\a ").unwrap());
+  assert_eq!("d", kvp.get("c\\").unwrap());
+  for input in [DATA_PROPERTIES, DATA_002, DATA_TASKS, input] {
+    assert_eq!(load_from_string(input), LoaderOptions::new().escapes(false).load_str(input).unwrap());
+  }
+}
+
+#[test]
+fn _0002() {
+  // All escape sequences are decoded in multiline keys and values.
+  let options = LoaderOptions::new().escapes(true).strict(true);
+  let kvp = options.load_str("\"a\\nb\"\n\"\\t\\\\\\\"\\u{41}\\u{1F600}\\u{0}\"\n").unwrap();
+  assert_eq!("\t\\\"A\u{1F600}\u{0}", kvp.get("a\nb").unwrap());
+  let options = LoaderOptions::new().escapes(true).strict(true).delimiters(&[("<[", "]>")]);
+  let kvp = options.load_str("<[a\\]>\n]>\n<[\\<\\[\\]\\>\\\"]>\n").unwrap();
+  assert_eq!("<[]>\"", kvp.get("a]>\n").unwrap());
+}
+
+#[test]
+fn _0003() {
+  // Escaped characters never close multiline keys and values.
+  let options = LoaderOptions::new().escapes(true).strict(true).markers(&['@']);
+  let kvp = options.load_str("a\n@b\\@\nc\\\\@\n").unwrap();
+  assert_eq!("b@\nc\\", kvp.get("a").unwrap());
+  let options = LoaderOptions::new().escapes(true).strict(true).delimiters(&[("<<<", ">>>")]);
+  let kvp = options.load_str("a\n<<<b>>\\>\n>\\>>\n>>>\n").unwrap();
+  assert_eq!("b>>>\n>>>\n", kvp.get("a").unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Unknown and malformed escape sequences are reported precisely and kept verbatim.
+  let options = LoaderOptions::new().escapes(true);
+  let input = "a\n\"\\q \\u41 \\u{}\\u{D800} \\u{1234567}\\\n\"\n";
+  let (kvp, diagnostics) = options.load_str_with_diagnostics(input);
+  assert_eq!("\\q \\u41 \\u{}\\u{D800} \\u{1234567}\\\n", kvp.get("a").unwrap());
+  let spans = diagnostics
+    .iter()
+    .map(|diagnostic| {
+      assert_eq!((&ErrorKind::InvalidEscape, Severity::Error), (diagnostic.kind(), diagnostic.severity()));
+      &input[diagnostic.span().range()]
+    })
+    .collect::<Vec<&str>>();
+  assert_eq!(vec!["\\q", "\\u", "\\u{}", "\\u{D800}", "\\u{123456", "\\"], spans);
+}
+
+#[test]
+fn _0005() {
+  // Escape sequence interrupted by the closing marker or by the end of input.
+  let options = LoaderOptions::new().escapes(true);
+  let (kvp, diagnostics) = options.load_str_with_diagnostics("\"a\\u{4\"\nb\n\"c\\");
+  assert_eq!("b", kvp.get("a\\u{4").unwrap());
+  let kinds = diagnostics.iter().map(|diagnostic| diagnostic.kind().code()).collect::<Vec<&str>>();
+  assert_eq!(vec!["invalid-escape", "invalid-escape", "unterminated-key"], kinds);
+}
+
+#[test]
+fn _0006() {
+  // Single-line keys and values and heredocs are always raw.
+  let options = LoaderOptions::new().escapes(true).heredoc(true).strict(true);
+  let kvp = options.load_str("a\\n\nb\\t\n<<END\nc\\n\nEND\n\"\\u{64}\"\n").unwrap();
+  assert_eq!("b\\t", kvp.get("a\\n").unwrap());
+  assert_eq!("d", kvp.get("c\\n").unwrap());
+  let events = options.parse_str("\"a\\tb\"\nc\n").collect::<Vec<Event>>();
+  let Event::Key { text, span, .. } = &events[0] else { panic!() };
+  assert_eq!(("a\tb", 0..6), (text.as_ref(), span.range()));
+}
+
+#[test]
+fn _0007() {
+  // Written keys and values with escapes are loaded back unchanged.
+  let mut kvp = KeyValuePairs::new();
+  kvp.insert("a\"\nb", "\\\"\n\"\n\\");
+  kvp.insert("c", "d\n\"");
+  let options = WriterOptions::new().escapes(true);
+  let output = options.format(&kvp).unwrap();
+  assert_eq!("\"a\\\"\nb\"\n\"\\\\\\\"\n\\\"\n\\\\\"\nc\n\"d\n\"\"\n", output);
+  assert_eq!(kvp, LoaderOptions::new().escapes(true).strict(true).load_str(&output).unwrap());
+  let mut document = LoaderOptions::new().escapes(true).load_document(&output).unwrap();
+  document.set("c", "e\"\nf").unwrap();
+  assert_eq!("\"a\\\"\nb\"\n\"\\\\\\\"\n\\\"\n\\\\\"\nc\n\"e\\\"\nf\"\n", document.to_string());
+  let kvp = LoaderOptions::new().escapes(true).strict(true).load_str(&document.to_string()).unwrap();
+  assert_eq!("e\"\nf", kvp.get("c").unwrap());
+}
+
+#[test]
+fn _0008() {
+  // Loading from reader gives the same result as loading from string.
+  let input = "\"a\\u{263A}\r\nb\"\r\n\"c\\\"\r\nd\"\r\n";
+  let options = LoaderOptions::new().escapes(true).strict(true);
+  let kvp = options.load_str(input).unwrap();
+  assert_eq!("c\"\nd", kvp.get("a\u{263A}\nb").unwrap());
+  assert_eq!(kvp, options.load_reader(input.as_bytes()).unwrap());
+}
@@ -0,0 +1,99 @@
+use super::*;
+use kivi::{load_from_string, Event, LoaderOptions};
+
+#[test]
+fn _0001() {
+  // Heredocs are disabled by default.
+  let kvp = load_from_string("a\n<<END\nb\nEND\n");
+  assert_eq!("<<END", kvp.get("a").unwrap());
+  assert_eq!("END", kvp.get("b").unwrap());
+  for input in [DATA_PROPERTIES, DATA_002, DATA_TASKS] {
+    assert_eq!(load_from_string(input), LoaderOptions::new().heredoc(true).load_str(input).unwrap());
+  }
+}
+
+#[test]
+fn _0002() {
+  // Heredoc keys and values end at the line consisting exactly of the terminator.
+  let options = LoaderOptions::new().heredoc(true).strict(true);
+  let input = "  <<KEY\nmultiline\nkey\nKEY\n<<END_OF_SCRIPT\necho \"END\"\n END\nEND \n\nEND_OF_SCRIPT\n";
+  let kvp = options.load_str(input).unwrap();
+  assert_eq!("echo \"END\"\n END\nEND \n", kvp.get("multiline\nkey").unwrap());
+}
+
+#[test]
+fn _0003() {
+  // Empty heredocs, terminator at the end of input and line endings.
+  let options = LoaderOptions::new().heredoc(true).strict(true);
+  let kvp = options.load_str("a\n<<E\nE\n<<E\n\n\nE\r\n<<X\r\nb\r\nc\r\nX").unwrap();
+  assert_eq!("", kvp.get("a").unwrap());
+  assert_eq!("b\nc", kvp.get("\n").unwrap());
+  assert_eq!(kvp, options.load_reader("a\n<<E\nE\n<<E\n\n\nE\r\n<<X\r\nb\r\nc\r\nX".as_bytes()).unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Lines that are not valid heredoc openings are single-line keys and values.
+  let options = LoaderOptions::new().heredoc(true).strict(true);
+  let kvp = options.load_str("<<\na\n<<A B\nc\nx<<A\nd\n").unwrap();
+  assert_eq!("a", kvp.get("<<").unwrap());
+  assert_eq!("c", kvp.get("<<A B").unwrap());
+  assert_eq!("d", kvp.get("x<<A").unwrap());
+}
+
+#[test]
+fn _0005() {
+  // Unterminated heredoc is reported.
+  let options = LoaderOptions::new().heredoc(true);
+  let (kvp, diagnostics) = options.load_str_with_diagnostics("a\n<<END\nb\nEND.\n");
+  assert!(kvp.is_empty());
+  assert_eq!("unterminated-value", diagnostics[0].code());
+  assert_eq!(2, diagnostics[0].span().start().line());
+}
+
+#[test]
+fn _0006() {
+  // Heredoc delimiters are reported with spans covering the opening line and the terminator.
+  let options = LoaderOptions::new().heredoc(true);
+  let input = "a\n  <<END\nb\nEND\n";
+  let events = options.parse_str(input).collect::<Vec<Event>>();
+  let Event::Value {
+    text,
+    span,
+    delimiter: Some(delimiter),
+  } = &events[1]
+  else {
+    panic!()
+  };
+  assert_eq!("b", text);
+  assert_eq!("<<END\nb\nEND", &input[span.range()]);
+  assert!(delimiter.is_heredoc());
+  assert_eq!(("<<END", "END"), (delimiter.open(), delimiter.close()));
+  assert!(matches!(text, std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn _0007() {
+  // Documents keep heredocs of changed values.
+  let options = LoaderOptions::new().heredoc(true);
+  let mut document = options.load_document("a\r\n<<END\r\nb\r\nEND\r\nc\r\nd\r\n").unwrap();
+  document.set("a", "\"x\"\ny").unwrap();
+  assert_eq!("a\r\n<<END\r\n\"x\"\r\ny\r\nEND\r\nc\r\nd\r\n", document.to_string());
+  document.set("a", "END\n").unwrap();
+  assert_eq!("a\r\n\"END\r\n\"\r\nc\r\nd\r\n", document.to_string());
+  assert_eq!(options.load_str(&document.to_string()).unwrap(), document.to_key_value_pairs());
+}
+
+#[test]
+fn _0008() {
+  // Changed keys and values opening a heredoc are enclosed in delimiters.
+  let options = LoaderOptions::new().heredoc(true);
+  let mut document = options.load_document("a\nb\n").unwrap();
+  document.set("a", " <<END ").unwrap();
+  document.insert("<<E-1", "<<x y").unwrap();
+  assert_eq!("a\n\" <<END \"\n\"<<E-1\"\n<<x y\n", document.to_string());
+  assert_eq!(options.load_str(&document.to_string()).unwrap(), document.to_key_value_pairs());
+  let mut document = LoaderOptions::new().load_document("a\nb\n").unwrap();
+  document.set("a", "<<END").unwrap();
+  assert_eq!("a\n<<END\n", document.to_string());
+}
@@ -0,0 +1,96 @@
+use super::*;
+use kivi::{load_from_string, Dedent, Document, Event, LineEnding, LoaderOptions, Parser};
+use std::borrow::Cow;
+
+#[test]
+fn _0001() {
+  // Line endings in multiline keys and values are normalized to LF by default.
+  let input = "\"a\r\nb\rc\nd\"\r\n\"e\r\n\"\r\n";
+  let kvp = load_from_string(input);
+  assert_eq!("e\n", kvp.get("a\nb\nc\nd").unwrap());
+  assert_eq!(kvp, LoaderOptions::new().preserve_line_endings(false).load_str(input).unwrap());
+  for input in [DATA_PROPERTIES, DATA_002, DATA_TASKS] {
+    assert_eq!(load_from_string(input), LoaderOptions::new().preserve_line_endings(true).load_str(input).unwrap());
+  }
+}
+
+#[test]
+fn _0002() {
+  // Original line endings are kept in multiline keys and values, all of them are line breaks.
+  let options = LoaderOptions::new().preserve_line_endings(true).strict(true);
+  let input = "\"a\r\nb\rc\nd\"\r\n\"e\r\n\"\rf\r\n  g  \r\n";
+  let kvp = options.load_str(input).unwrap();
+  assert_eq!("e\r\n", kvp.get("a\r\nb\rc\nd").unwrap());
+  assert_eq!("g", kvp.get("f").unwrap());
+  let events = options.parse_str(input).collect::<Vec<Event>>();
+  let Event::Value { text, span, .. } = &events[1] else { panic!() };
+  assert!(matches!(text, Cow::Borrowed(_)));
+  assert_eq!((5, 1, 6, 2), (span.start().line(), span.start().column(), span.end().line(), span.end().column()));
+}
+
+#[test]
+fn _0003() {
+  // Heredocs keep original line endings, except the line endings around the content.
+  let options = LoaderOptions::new().preserve_line_endings(true).heredoc(true).strict(true);
+  let kvp = options.load_str("script\r\n<<END\r\n@echo off\r\necho done\r\n\r\nEND\r\na\r<<E\rb\nE\r").unwrap();
+  assert_eq!("@echo off\r\necho done\r\n", kvp.get("script").unwrap());
+  assert_eq!("b", kvp.get("a").unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Dedent and trimming of blank lines keep original line endings.
+  let options = LoaderOptions::new()
+    .preserve_line_endings(true)
+    .dedent(Dedent::Common)
+    .trim_first_blank_line(true)
+    .trim_last_blank_line(true);
+  let kvp = options.load_str("a\r\n\"\r\n  b\r\n    c\r\n  \"\r\n").unwrap();
+  assert_eq!("b\r\n  c", kvp.get("a").unwrap());
+}
+
+#[test]
+fn _0005() {
+  // Style of line endings used in the input is reported.
+  let mut parser = Parser::new("a\rb\r\nc\n");
+  assert_eq!(None, parser.line_ending());
+  parser.next();
+  assert_eq!(Some(LineEnding::Cr), parser.line_ending());
+  for (input, line_ending) in [
+    ("a\nb\r\n", LineEnding::Lf),
+    ("a\r\nb\n", LineEnding::CrLf),
+    ("a\rb\n", LineEnding::Cr),
+    ("a", LineEnding::Lf),
+  ] {
+    assert_eq!(line_ending, Document::parse(input).line_ending());
+    assert_eq!(line_ending, LineEnding::detect(input).unwrap_or_default());
+  }
+}
+
+#[test]
+fn _0006() {
+  // Documents with original line endings represent values containing carriage returns.
+  let options = LoaderOptions::new().preserve_line_endings(true);
+  let mut document = options.load_document("a\r\n\"b\r\nc\"\r\nd\r\ne\r\n").unwrap();
+  assert_eq!(LineEnding::CrLf, document.line_ending());
+  document.set("a", "f\ng\r\n").unwrap();
+  document.set("d", "h\r").unwrap();
+  assert_eq!("a\r\n\"f\ng\r\n\"\r\nd\r\n\"h\r\"\r\n", document.to_string());
+  let kvp = options.load_str(&document.to_string()).unwrap();
+  assert_eq!("f\ng\r\n", kvp.get("a").unwrap());
+  assert_eq!("h\r", kvp.get("d").unwrap());
+  let mut document = LoaderOptions::new().load_document("a\r\nb\r\n").unwrap();
+  assert!(document.set("a", "c\r\n").is_err());
+}
+
+#[test]
+fn _0007() {
+  // Loading from reader gives the same result as loading from string.
+  let input = "\"a\r\nb\rc\nd\"\r\n\"e\r\n\"\rf\r\n\"g\r";
+  let options = LoaderOptions::new().preserve_line_endings(true);
+  assert_eq!(options.load_str(input).unwrap(), options.load_reader(input.as_bytes()).unwrap());
+  let mut parser = options.parse_reader(input.as_bytes());
+  let Some(Event::Key { text, .. }) = parser.next() else { panic!() };
+  assert_eq!("a\r\nb\rc\nd", text);
+  assert_eq!(Some(LineEnding::CrLf), parser.line_ending());
+}
@@ -0,0 +1,130 @@
+use super::*;
+use kivi::{load_from_string, load_from_string_markers, DuplicatePolicy, ErrorKind, LoaderOptions};
+use std::io;
+
+#[test]
+fn default_options_should_work() {
+  for input in [DATA_001, DATA_002, DATA_003, DATA_004, DATA_005, DATA_006, DATA_PROPERTIES, DATA_TASKS] {
+    assert_eq!(load_from_string(input), LoaderOptions::new().load_str(input).unwrap());
+  }
+  assert_eq!(LoaderOptions::default(), LoaderOptions::new());
+}
+
+#[test]
+fn markers_should_work() {
+  let kvp = LoaderOptions::new().markers(&['@', '~', '^']).load_str(DATA_ISSUES).unwrap();
+  assert_eq!(load_from_string_markers(DATA_ISSUES, &['@', '~', '^']), kvp);
+}
+
+#[test]
+fn key_and_value_markers_should_work() {
+  let options = LoaderOptions::new().key_markers(&['@']).value_markers(&['"', '`']).strict(true);
+  let kvp = options.load_str("a\"b\n`c\nd`\n@e\nf@\n\"g@h\"\ni\n@j\n").unwrap();
+  assert_eq!("c\nd", kvp.get("a\"b").unwrap());
+  assert_eq!("g@h", kvp.get("e\nf").unwrap());
+  assert_eq!("@j", kvp.get("i").unwrap());
+  let options = LoaderOptions::new().markers(&['@']).value_markers(&['~']);
+  assert_eq!("@b@\n", options.load_str("@~a~\n@\n~@b@\n~\n").unwrap().get("~a~\n").unwrap());
+}
+
+#[test]
+fn duplicates_should_work() {
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(DATA_TASKS).unwrap();
+  assert_eq!(5, kvp.len());
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::FirstWins).load_str(DATA_TASKS).unwrap();
+  assert_eq!("Build a separate server", kvp.get("Issue1").unwrap());
+}
+
+#[test]
+fn strict_mode_should_work() {
+  let kvp = LoaderOptions::new().load_str("a\nb\n\"c").unwrap();
+  assert_eq!(1, kvp.len());
+  let error = LoaderOptions::new().strict(true).load_str("a\nb\n\"c").unwrap_err();
+  assert_eq!(&ErrorKind::UnterminatedKey, error.kind());
+  assert_eq!(3, error.line());
+  let error = LoaderOptions::new().strict(true).duplicates(DuplicatePolicy::Reject).load_str(DATA_TASKS).unwrap_err();
+  assert_eq!(&ErrorKind::DuplicateKey, error.kind());
+  assert_eq!(7, error.line());
+}
+
+#[test]
+fn strict_mode_should_not_fail_on_warnings() {
+  let kvp = LoaderOptions::new().strict(true).load_str(DATA_TASKS).unwrap();
+  assert_eq!(3, kvp.len());
+}
+
+#[test]
+fn max_key_length_should_work() {
+  let options = LoaderOptions::new().max_key_length(3);
+  assert_eq!(2, options.load_str("abc\n1\n   def   \n2\n").unwrap().len());
+  let (kvp, diagnostics) = options.load_str_with_diagnostics("abc\n1\nabcd\n2\nx\n3\n");
+  assert_eq!(1, kvp.len());
+  assert_eq!(1, diagnostics.len());
+  assert_eq!("key-too-long", diagnostics[0].code());
+  assert_eq!(3, diagnostics[0].span().start().line());
+  let error = options.clone().strict(true).load_str("\"ab\ncd\"\n1\n").unwrap_err();
+  assert_eq!(&ErrorKind::KeyTooLong, error.kind());
+  assert_eq!(1, error.line());
+  assert_eq!(1, error.column());
+}
+
+#[test]
+fn max_value_length_should_work() {
+  let options = LoaderOptions::new().max_value_length(3);
+  assert_eq!(2, options.load_str("a\nabc\nb\n   def   ").unwrap().len());
+  let (kvp, diagnostics) = options.load_str_with_diagnostics("a\nabc\nb\n  \"d\nef\"\n");
+  assert_eq!(1, kvp.len());
+  assert_eq!(1, diagnostics.len());
+  assert_eq!("value-too-long", diagnostics[0].code());
+  assert_eq!(4, diagnostics[0].span().start().line());
+  assert_eq!(3, diagnostics[0].span().start().column());
+}
+
+#[test]
+fn long_whitespace_runs_should_be_bounded() {
+  let options = LoaderOptions::new().max_key_length(3).max_value_length(3).strict(true);
+  let input = format!("{}a\n{}b{}\n", " ".repeat(1000), " ".repeat(500), " ".repeat(500));
+  assert_eq!("b", options.load_str(&input).unwrap().get("a").unwrap());
+  for input in [format!("a\n{}b\n", " ".repeat(1_000_000)), format!("a\nb{}\n", " ".repeat(1_000_000))] {
+    let error = options.load_str(&input).unwrap_err();
+    assert_eq!(&ErrorKind::ValueTooLong, error.kind());
+    assert_eq!((2, 1), (error.line(), error.column()));
+  }
+  let error = options.load_str(&format!("{}a\nb\n", "\t".repeat(1_000_000))).unwrap_err();
+  assert_eq!(&ErrorKind::KeyTooLong, error.kind());
+  let input = format!("{}{}\nb\n", " ".repeat(200_000), "a".repeat(200_000));
+  assert_eq!(1, LoaderOptions::new().load_str(&input).unwrap().len());
+}
+
+#[test]
+fn max_pairs_should_work() {
+  let options = LoaderOptions::new().max_pairs(2);
+  assert_eq!(2, options.load_str(DATA_TASKS).unwrap().len());
+  let (kvp, diagnostics) = options.load_str_with_diagnostics(DATA_001);
+  assert_eq!(2, kvp.len());
+  assert_eq!(1, diagnostics.len());
+  assert_eq!("too-many-pairs", diagnostics[0].code());
+  assert_eq!(7, diagnostics[0].span().start().line());
+  let error = options.clone().duplicates(DuplicatePolicy::KeepAll).strict(true).load_str(DATA_TASKS).unwrap_err();
+  assert_eq!(&ErrorKind::TooManyPairs, error.kind());
+  assert_eq!(7, error.line());
+}
+
+#[test]
+fn loading_from_file_should_work() {
+  let kvp = LoaderOptions::new().load_file(FILE_002).unwrap();
+  assert_eq!(4, kvp.len());
+  let (kvp, diagnostics) = LoaderOptions::new().load_file_with_diagnostics(FILE_ISSUES).unwrap();
+  assert_eq!(3, kvp.len());
+  assert!(diagnostics.is_empty());
+  let error = LoaderOptions::new().load_file("tests/data/non-existing.kivi").unwrap_err();
+  assert_eq!(&ErrorKind::Io(io::ErrorKind::NotFound), error.kind());
+}
+
+#[test]
+fn loading_from_reader_should_work() {
+  let kvp = LoaderOptions::new().load_reader(DATA_002.as_bytes()).unwrap();
+  assert_eq!(load_from_string(DATA_002), kvp);
+  let error = LoaderOptions::new().load_reader(&[0xC3_u8, 0x28][..]).unwrap_err();
+  assert_eq!(&ErrorKind::Io(io::ErrorKind::InvalidData), error.kind());
+}
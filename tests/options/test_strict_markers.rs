@@ -0,0 +1,40 @@
+use super::*;
+use kivi::{load_from_string, LoaderOptions};
+
+#[test]
+fn _0001() {
+  // Markers are recognized anywhere in the line by default.
+  let input = "a\nabc\"def\nghi\"\n";
+  assert_eq!("def\nghi", load_from_string(input).get("a").unwrap());
+  assert_eq!("def\nghi", LoaderOptions::new().strict_markers(false).load_str(input).unwrap().get("a").unwrap());
+}
+
+#[test]
+fn _0002() {
+  // Markers are ordinary characters when not starting the line.
+  let options = LoaderOptions::new().strict_markers(true).strict(true);
+  let kvp = options.load_str("a\"b\nabc\"def\n\"c\nd\"\n  \"e\"\n").unwrap();
+  assert_eq!("abc\"def", kvp.get("a\"b").unwrap());
+  assert_eq!("e", kvp.get("c\nd").unwrap());
+}
+
+#[test]
+fn _0003() {
+  // Markers inside multiline keys and values are not affected.
+  let options = LoaderOptions::new().markers(&['"', '@']).strict_markers(true);
+  let kvp = options.load_str("@a \"b\" @c\n@\n\"d@\n  \"e\nf\"\n").unwrap();
+  assert_eq!("d@\n  \"e\nf", kvp.get("a \"b\" @c\n").unwrap());
+}
+
+#[test]
+fn _0004() {
+  // Loading the same input with strict markers from string and reader gives the same result.
+  let options = LoaderOptions::new().markers(&['@', '~', '^']).strict_markers(true);
+  for input in [DATA_PROPERTIES, DATA_ISSUES, DATA_TASKS, "a\nb \"c\" d\n"] {
+    assert_eq!(options.load_str(input).unwrap(), options.load_reader(input.as_bytes()).unwrap());
+  }
+  assert_eq!(
+    load_from_string(DATA_PROPERTIES),
+    LoaderOptions::new().strict_markers(true).load_str(DATA_PROPERTIES).unwrap()
+  );
+}
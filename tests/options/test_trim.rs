@@ -0,0 +1,105 @@
+use super::*;
+use kivi::{load_from_string, Event, LoaderOptions, TrimPolicy};
+
+#[test]
+fn _0001() {
+  // Single-line keys and values are trimmed at both ends by default.
+  let input = "  a  \n\tb\t\n";
+  assert_eq!("b", load_from_string(input).get("a").unwrap());
+  for input in [DATA_PROPERTIES, DATA_002, DATA_TASKS, input] {
+    assert_eq!(load_from_string(input), LoaderOptions::new().trim(TrimPolicy::Both).load_str(input).unwrap());
+  }
+}
+
+#[test]
+fn _0002() {
+  // Trailing whitespaces that matter survive without a multiline marker.
+  let options = LoaderOptions::new().value_trim(TrimPolicy::Start);
+  let kvp = options.load_str("prompt  \n  > \r\ncontinuation\n  ...  \n").unwrap();
+  assert_eq!("> ", kvp.get("prompt").unwrap());
+  assert_eq!("...  ", kvp.get("continuation").unwrap());
+  let options = LoaderOptions::new().value_trim(TrimPolicy::End);
+  let kvp = options.load_str("code\n    return;  \n").unwrap();
+  assert_eq!("    return;", kvp.get("code").unwrap());
+  let options = LoaderOptions::new().trim(TrimPolicy::Preserve);
+  let kvp = options.load_str(" a \n b \n").unwrap();
+  assert_eq!(" b ", kvp.get(" a ").unwrap());
+  assert_eq!(None, kvp.get("a"));
+}
+
+#[test]
+fn _0003() {
+  // Lines containing only whitespaces are blank lines and multiline keys and values are never trimmed.
+  let options = LoaderOptions::new().trim(TrimPolicy::Preserve);
+  let kvp = options.load_str("  \na\n \t \nb\n  \"c \"\n  d\n").unwrap();
+  assert_eq!("b", kvp.get("a").unwrap());
+  assert_eq!("  d", kvp.get("c ").unwrap());
+  assert_eq!(2, kvp.len());
+}
+
+#[test]
+fn _0004() {
+  // Spans cover the preserved whitespaces.
+  let options = LoaderOptions::new().key_trim(TrimPolicy::End).value_trim(TrimPolicy::Preserve);
+  let input = "  ä \n ö  \n";
+  let events = options.parse_str(input).collect::<Vec<Event>>();
+  let Event::Key { text, span, .. } = &events[0] else { panic!() };
+  assert_eq!("  ä", text);
+  assert_eq!((0..4, 1, 4), (span.range(), span.start().column(), span.end().column()));
+  let Event::Value { text, span, .. } = &events[1] else { panic!() };
+  assert_eq!(" ö  ", text);
+  assert_eq!((6..11, 1, 5), (span.range(), span.start().column(), span.end().column()));
+}
+
+#[test]
+fn _0005() {
+  // Comments are recognized regardless of the trim policy.
+  let options = LoaderOptions::new().comments(&["#"]).trim(TrimPolicy::Preserve);
+  let kvp = options.load_str("  # comment\na\n  b \n").unwrap();
+  assert_eq!("  b ", kvp.get("a").unwrap());
+  assert_eq!(vec!["comment"], kvp.comments("a").collect::<Vec<&str>>());
+}
+
+#[test]
+fn _0006() {
+  // The token represents an empty value, but not an empty key.
+  let options = LoaderOptions::new().empty_value("(empty)").trim(TrimPolicy::Preserve);
+  let kvp = options.load_str("a\n  (empty) \n(empty)\nb\nc\n\"(empty)\"\nd\n(empty)x\n").unwrap();
+  assert_eq!("", kvp.get("a").unwrap());
+  assert_eq!("b", kvp.get("(empty)").unwrap());
+  assert_eq!("(empty)", kvp.get("c").unwrap());
+  assert_eq!("(empty)x", kvp.get("d").unwrap());
+  assert_eq!(load_from_string("a\n(empty)\n"), LoaderOptions::new().empty_value("").load_str("a\n(empty)\n").unwrap());
+}
+
+#[test]
+fn _0007() {
+  // Loading from reader gives the same result as loading from string.
+  let input = "  a \r\n  b  \r\n\r\nc\n <none> \n d\n\"e\"\n";
+  let options = LoaderOptions::new().key_trim(TrimPolicy::Start).value_trim(TrimPolicy::End).empty_value("<none>");
+  let kvp = options.load_str(input).unwrap();
+  assert_eq!("  b", kvp.get("a ").unwrap());
+  assert_eq!("", kvp.get("c").unwrap());
+  assert_eq!("e", kvp.get("d").unwrap());
+  assert_eq!(kvp, options.load_reader(input.as_bytes()).unwrap());
+}
+
+#[test]
+fn _0008() {
+  // Changed keys and values are written so they are loaded back unchanged.
+  let options = LoaderOptions::new().empty_value("-").value_trim(TrimPolicy::Start).key_trim(TrimPolicy::Preserve);
+  let mut document = options.load_document("a\n  b\n").unwrap();
+  document.set("a", " - ").unwrap();
+  document.insert(" c ", "d  ").unwrap();
+  document.insert("e", "  f").unwrap();
+  document.insert("-", "").unwrap();
+  assert_eq!("a\n  \" - \"\n c \nd  \ne\n\"  f\"\n-\n\"\"\n", document.to_string());
+  assert_eq!(document.to_key_value_pairs(), options.load_str(&document.to_string()).unwrap());
+  let options = LoaderOptions::new().value_trim(TrimPolicy::End);
+  let mut document = options.load_document("a\nb\n").unwrap();
+  document.set("a", "  c").unwrap();
+  document.insert("d", "e  ").unwrap();
+  document.insert("f", " ").unwrap();
+  assert_eq!("a\n  c\nd\n\"e  \"\nf\n\" \"\n", document.to_string());
+  assert_eq!(document.to_key_value_pairs(), options.load_str(&document.to_string()).unwrap());
+}
@@ -0,0 +1,192 @@
+use super::*;
+use kivi::{ErrorKind, Event, LoaderOptions, Parser};
+use std::io;
+use std::io::{BufReader, Read};
+
+/// Formats events as `kind:line:column-line:column`, followed by the text of keys and values.
+fn fmt<'a>(events: impl Iterator<Item = Event<'a>>) -> String {
+  events
+    .map(|event| match event {
+      Event::Key { text, span, delimiter, .. } => format!(
+        "{}:{}:{}-{}:{}={:?}",
+        if delimiter.is_some() { "K*" } else { "K" },
+        span.start().line(),
+        span.start().column(),
+        span.end().line(),
+        span.end().column(),
+        text
+      ),
+      Event::Value { text, span, delimiter, .. } => format!(
+        "{}:{}:{}-{}:{}={:?}",
+        if delimiter.is_some() { "V*" } else { "V" },
+        span.start().line(),
+        span.start().column(),
+        span.end().line(),
+        span.end().column(),
+        text
+      ),
+      Event::Comment { text, span } => format!(
+        "C:{}:{}-{}:{}={:?}",
+        span.start().line(),
+        span.start().column(),
+        span.end().line(),
+        span.end().column(),
+        text
+      ),
+      Event::BlankLine { span } => format!("B:{}:{}-{}:{}", span.start().line(), span.start().column(), span.end().line(), span.end().column()),
+      Event::Error { kind, span } => format!("E[{}]:{}:{}", kind.code(), span.start().line(), span.start().column()),
+      Event::Warning { kind, span } => format!("W[{}]:{}:{}", kind.code(), span.start().line(), span.start().column()),
+    })
+    .collect::<Vec<String>>()
+    .join(",")
+}
+
+/// Formats opening and closing delimiters of keys and values.
+fn delimiters<'a>(events: impl Iterator<Item = Event<'a>>) -> String {
+  events
+    .filter_map(|event| match event {
+      Event::Key { delimiter, .. } | Event::Value { delimiter, .. } => Some(delimiter.map(|d| format!("{}{}", d.open(), d.close())).unwrap_or_default()),
+      _ => None,
+    })
+    .collect::<Vec<String>>()
+    .join(",")
+}
+
+fn eq(input: &str, expected: &str) {
+  assert_eq!(expected, fmt(Parser::new(input)));
+}
+
+/// Reader returning an error after reading all bytes.
+struct FailingReader<'a>(&'a [u8]);
+
+impl Read for FailingReader<'_> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if self.0.is_empty() {
+      return Err(io::Error::other("connection reset"));
+    }
+    let n = self.0.read(buf)?;
+    Ok(n)
+  }
+}
+
+#[test]
+fn _0001() {
+  // Empty input.
+  eq("", "");
+}
+
+#[test]
+fn _0002() {
+  // Single line keys and values.
+  eq("a\nb\n", r#"K:1:1-1:2="a",V:2:1-2:2="b""#);
+  eq("  a  \n  b", r#"K:1:3-1:4="a",V:2:3-2:4="b""#);
+}
+
+#[test]
+fn _0003() {
+  // Multiline keys and values.
+  eq("\"a\nb\"\n\"c\"\n", r#"K*:1:1-2:3="a\nb",V*:3:1-3:4="c""#);
+}
+
+#[test]
+fn _0004() {
+  // Blank lines.
+  eq("\na\n  \nb\n\n", r#"B:1:1-1:1,K:2:1-2:2="a",B:3:1-3:3,V:4:1-4:2="b",B:5:1-5:1"#);
+  eq("a\nb\n  ", r#"K:1:1-1:2="a",V:2:1-2:2="b",B:3:1-3:3"#);
+  eq("\"a\"\n\n\"b\"\n", r#"K*:1:1-1:4="a",B:2:1-2:1,V*:3:1-3:4="b""#);
+}
+
+#[test]
+fn _0005() {
+  // Errors and warnings.
+  eq("a\nb\nc\n", r#"K:1:1-1:2="a",V:2:1-2:2="b",K:3:1-3:2="c",E[missing-value]:3:1"#);
+  eq("a\nb\nc", r#"K:1:1-1:2="a",V:2:1-2:2="b",K:3:1-3:2="c",E[missing-value]:3:1"#);
+  eq("a\n\"b\" c\n", r#"K:1:1-1:2="a",W[text-after-marker]:2:4,E[unterminated-value]:2:1"#);
+  eq("\"a\n", r#"E[unterminated-key]:1:1"#);
+}
+
+#[test]
+fn _0006() {
+  // Markers and limits taken from options.
+  let options = LoaderOptions::new().markers(&['@', '~']).max_value_length(3);
+  assert_eq!(
+    r#"K*:1:1-1:4="a",V*:2:1-2:4="b",K:3:1-3:2="c",E[value-too-long]:4:1"#,
+    fmt(options.parse_str("@a@\n~b~\nc\ndefg\nh\ni\n"))
+  );
+}
+
+#[test]
+fn _0007() {
+  // Delimiters are reported.
+  let options = LoaderOptions::new().markers(&['@', '~']);
+  assert_eq!("@@,~~,,", delimiters(options.parse_str("@a@\n~b~\nc\nd\n")));
+  let options = LoaderOptions::new().delimiters(&[("[", "]"), ("<<<", ">>>")]);
+  assert_eq!("[],<<<>>>", delimiters(options.parse_str("[a]\n<<<b>>>\n")));
+}
+
+#[test]
+fn _0008() {
+  // Comments are reported outside multiline keys and values.
+  let options = LoaderOptions::new().comments(&["#", "//"]);
+  assert_eq!(
+    r#"C:1:3-1:8="a b",K:2:1-2:2="c",C:3:1-3:4="d",V*:4:1-5:5="\n# e",K:6:1-6:4="f#g",V:7:1-7:2="h",C:8:1-8:4="i""#,
+    fmt(options.parse_str("  # a b  \nc\n//d\n\"\n# e\"\nf#g\nh\n//i"))
+  );
+  assert_eq!(r#"K:1:1-1:2="a",C:2:1-2:2="",E[missing-value]:1:1"#, fmt(options.parse_str("a\n#")));
+  assert_eq!(r#"K:1:1-1:2="/",V:2:1-2:2="a""#, fmt(options.parse_str("/\na")));
+  assert_eq!(fmt(options.parse_str(DATA_PROPERTIES)), fmt(Parser::new(DATA_PROPERTIES)));
+}
+
+#[test]
+fn _0009() {
+  // Spans of multiline keys and values include multi-character delimiters.
+  let options = LoaderOptions::new().delimiters(&[("<<<", ">>>"), ("«", "»")]);
+  assert_eq!(r#"K*:1:2-2:6="a\n>>",V*:3:1-3:5="b»""#, fmt(options.parse_str("x<<<a\n>>>>>\n«b»»\n")));
+  assert_eq!(
+    r#"K:1:1-1:2="a",W[text-after-marker]:2:8,E[unterminated-value]:2:1"#,
+    fmt(options.parse_str("a\n<<<b>>> c\n>>\n"))
+  );
+}
+
+#[test]
+fn parsing_is_lazy() {
+  let mut parser = Parser::new("a\nb\n\"c");
+  assert!(matches!(parser.next(), Some(Event::Key { text, .. }) if text == "a"));
+  assert!(matches!(parser.next(), Some(Event::Value { text, .. }) if text == "b"));
+  assert!(matches!(
+    parser.next(),
+    Some(Event::Error {
+      kind: ErrorKind::UnterminatedKey,
+      ..
+    })
+  ));
+  assert_eq!(None, parser.next());
+  assert_eq!(None, parser.next());
+}
+
+#[test]
+fn parsing_from_reader_should_work() {
+  assert_eq!(fmt(Parser::new(DATA_PROPERTIES)), fmt(Parser::from_reader(DATA_PROPERTIES.as_bytes())));
+  assert_eq!(fmt(Parser::new(DATA_005)), fmt(Parser::from_reader(BufReader::with_capacity(1, DATA_005.as_bytes()))));
+}
+
+#[test]
+fn parsing_from_failing_reader_should_report_error() {
+  let events = Parser::from_reader(BufReader::new(FailingReader(b"a\nb\nc"))).collect::<Vec<Event>>();
+  assert_eq!(3, events.len());
+  assert!(matches!(
+    &events[2],
+    Event::Error {
+      kind: ErrorKind::Io(io::ErrorKind::Other),
+      ..
+    }
+  ));
+}
+
+#[test]
+fn parser_should_be_send() {
+  fn assert_send<T: Send>(_: &T) {}
+  assert_send(&Parser::new("a\nb\n"));
+  assert_send(&Parser::from_reader("a\nb\n".as_bytes()));
+  assert_send(&LoaderOptions::new().parse_reader(BufReader::new("a\nb\n".as_bytes())));
+}
@@ -0,0 +1,5 @@
+use super::*;
+
+mod test_save_to_file;
+mod test_writer;
+mod test_writer_options;
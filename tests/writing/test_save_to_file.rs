@@ -0,0 +1,103 @@
+use super::*;
+use kivi::{load_from_file, load_from_string, ErrorKind, KeyValuePairs, WriterOptions};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Creates an empty directory for the test with the specified name.
+fn test_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("kivi-{}-{}", std::process::id(), name));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+/// Returns sorted names of files in the directory.
+fn file_names(dir: &PathBuf) -> Vec<String> {
+  let mut names = fs::read_dir(dir)
+    .unwrap()
+    .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+    .collect::<Vec<String>>();
+  names.sort();
+  names
+}
+
+#[test]
+fn saving_new_file_should_work() {
+  let dir = test_dir("save-new");
+  let path = dir.join("config.kivi");
+  let kvp = load_from_string(DATA_002);
+  kvp.save_to_file(&path, &WriterOptions::new()).unwrap();
+  assert_eq!(kvp, load_from_file(&path).unwrap());
+  assert_eq!(vec!["config.kivi"], file_names(&dir));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saving_existing_file_should_work() {
+  let dir = test_dir("save-existing");
+  let path = dir.join("config.kivi");
+  fs::write(&path, DATA_PROPERTIES).unwrap();
+  let mut kvp = load_from_file(&path).unwrap();
+  kvp.set("host", "0.0.0.0");
+  kvp.save_to_file(&path, &WriterOptions::new()).unwrap();
+  assert_eq!("0.0.0.0", load_from_file(&path).unwrap().get("host").unwrap());
+  assert_eq!(vec!["config.kivi"], file_names(&dir));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saving_with_backup_should_work() {
+  let dir = test_dir("save-backup");
+  let path = dir.join("config.kivi");
+  let options = WriterOptions::new().backup(true);
+  let mut kvp = KeyValuePairs::new();
+  kvp.insert("a", "1");
+  kvp.save_to_file(&path, &options).unwrap();
+  assert_eq!(vec!["config.kivi"], file_names(&dir));
+  kvp.insert("a", "2");
+  kvp.save_to_file(&path, &options).unwrap();
+  assert_eq!(vec!["config.kivi", "config.kivi.bak"], file_names(&dir));
+  assert_eq!("a\n1\n", fs::read_to_string(dir.join("config.kivi.bak")).unwrap());
+  assert_eq!("a\n2\n", fs::read_to_string(&path).unwrap());
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saving_unrepresentable_value_should_fail() {
+  let dir = test_dir("save-unrepresentable");
+  let path = dir.join("config.kivi");
+  fs::write(&path, "a\n1\n").unwrap();
+  let mut kvp = KeyValuePairs::new();
+  kvp.insert("a", "\"\n");
+  let error = kvp.save_to_file(&path, &WriterOptions::new().backup(true)).unwrap_err();
+  assert_eq!(&ErrorKind::Unrepresentable, error.kind());
+  assert_eq!("a\n1\n", fs::read_to_string(&path).unwrap());
+  assert_eq!(vec!["config.kivi"], file_names(&dir));
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn saving_to_missing_directory_should_fail() {
+  let dir = test_dir("save-missing");
+  let error = KeyValuePairs::new()
+    .save_to_file(dir.join("missing").join("config.kivi"), &WriterOptions::new())
+    .unwrap_err();
+  assert_eq!(&ErrorKind::Io(io::ErrorKind::NotFound), error.kind());
+  assert!(file_names(&dir).is_empty());
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn saving_should_preserve_permissions() {
+  use std::os::unix::fs::PermissionsExt;
+  let dir = test_dir("save-permissions");
+  let path = dir.join("config.kivi");
+  fs::write(&path, "a\n1\n").unwrap();
+  fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+  load_from_string("a\n2\n").save_to_file(&path, &WriterOptions::new()).unwrap();
+  assert_eq!(0o640, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+  assert_eq!("a\n2\n", fs::read_to_string(&path).unwrap());
+  fs::remove_dir_all(&dir).unwrap();
+}
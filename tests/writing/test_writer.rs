@@ -0,0 +1,131 @@
+use super::*;
+use kivi::{load_from_string, DuplicatePolicy, ErrorKind, KeyValuePairs, LoaderOptions};
+use std::io;
+use std::io::Write;
+
+/// Writer failing after accepting the specified number of bytes.
+struct FailingWriter(usize);
+
+impl Write for FailingWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    if buf.len() > self.0 {
+      return Err(io::Error::other("disk full"));
+    }
+    self.0 -= buf.len();
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+fn roundtrip(kvp: &KeyValuePairs) {
+  let output = kvp.to_kivi_string().unwrap();
+  assert_eq!(kvp, &load_from_string(&output), "{:?}", output);
+}
+
+fn eq(key: &str, value: &str, expected: &str) {
+  let mut kvp = KeyValuePairs::new();
+  kvp.insert(key, value);
+  assert_eq!(expected, kvp.to_kivi_string().unwrap());
+  roundtrip(&kvp);
+}
+
+#[test]
+fn _0001() {
+  // Loaded documents are written back unchanged.
+  for input in [DATA_PROPERTIES, DATA_001, DATA_002, DATA_003, DATA_004, DATA_005, DATA_006, DATA_TASKS] {
+    roundtrip(&load_from_string(input));
+  }
+}
+
+#[test]
+fn _0002() {
+  // Single line keys and values.
+  eq("a", "b", "a\nb\n");
+  eq("a b", "c\td", "a b\nc\td\n");
+  eq("zażółć", "gęślą jaźń", "zażółć\ngęślą jaźń\n");
+  eq("'a'", "`b`", "'a'\n`b`\n");
+}
+
+#[test]
+fn _0003() {
+  // Keys and values enclosed in markers.
+  eq("", "", "\"\"\n\"\"\n");
+  eq(" a", "b ", "\" a\"\n\"b \"\n");
+  eq("a\nb", "\n", "\"a\nb\"\n\"\n\"\n");
+  eq("a\"b", "\"", "\"a\"b\"\n\"\"\"\n");
+  eq("a\"", "\"\"", "\"a\"\"\n\"\"\"\"\n");
+  eq("\u{a0}a", "a\u{2003}", "\"\u{a0}a\"\n\"a\u{2003}\"\n");
+  eq("a\0b", "\0", "a\0b\n\0\n");
+}
+
+#[test]
+fn _0004() {
+  // Keys and values that can not be represented.
+  for (key, value) in [("a\"\nb", "c"), ("a", "b\"\n"), ("a\rb", "c"), ("a", "b\r\nc")] {
+    let mut kvp = KeyValuePairs::new();
+    kvp.insert(key, value);
+    assert_eq!(&ErrorKind::Unrepresentable, kvp.to_kivi_string().unwrap_err().kind());
+    assert_eq!(&ErrorKind::Unrepresentable, kvp.write_to(io::sink()).unwrap_err().kind());
+  }
+}
+
+#[test]
+fn _0005() {
+  // Duplicated keys are written in document order.
+  let kvp = LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(DATA_TASKS).unwrap();
+  let output = kvp.to_kivi_string().unwrap();
+  assert_eq!(kvp, LoaderOptions::new().duplicates(DuplicatePolicy::KeepAll).load_str(&output).unwrap());
+}
+
+#[test]
+fn _0006() {
+  // Writing to writer.
+  let kvp = load_from_string(DATA_002);
+  let mut output = vec![];
+  kvp.write_to(&mut output).unwrap();
+  assert_eq!(kvp.to_kivi_string().unwrap().as_bytes(), output.as_slice());
+  let error = kvp.write_to(FailingWriter(3)).unwrap_err();
+  assert_eq!(&ErrorKind::Io(io::ErrorKind::Other), error.kind());
+  assert_eq!("I/O error: disk full", error.to_string());
+}
+
+#[test]
+fn _0007() {
+  // Edited key-value pairs.
+  let mut kvp = load_from_string(DATA_PROPERTIES);
+  kvp.insert("host", "0.0.0.0");
+  kvp.remove("description");
+  kvp.insert("notes", "first line\nsecond line");
+  roundtrip(&kvp);
+}
+
+#[test]
+fn _0008() {
+  // All short texts built from characters significant for the parser are written back unchanged.
+  let alphabet = ['a', ' ', '\n', '"', '\t'];
+  let mut texts = vec![String::new()];
+  for _ in 0..4 {
+    let longer = texts
+      .iter()
+      .flat_map(|text| alphabet.iter().map(move |ch| format!("{}{}", text, ch)))
+      .collect::<Vec<String>>();
+    texts.extend(longer);
+  }
+  texts.sort();
+  texts.dedup();
+  for text in &texts {
+    let mut kvp = KeyValuePairs::new();
+    kvp.insert(text.as_str(), "value");
+    kvp.insert("key", text.as_str());
+    match kvp.to_kivi_string() {
+      Ok(output) => assert_eq!(kvp, load_from_string(&output), "{:?}", output),
+      Err(error) => {
+        assert_eq!(&ErrorKind::Unrepresentable, error.kind());
+        assert!(text.contains("\"\n"), "{:?}", text);
+      }
+    }
+  }
+}
@@ -0,0 +1,142 @@
+use super::*;
+use kivi::{load_from_string, load_from_string_markers, Document, ErrorKind, KeyValuePairs, LineEnding, LoaderOptions, WriterOptions};
+
+fn kvp(key: &str, value: &str) -> KeyValuePairs {
+  let mut kvp = KeyValuePairs::new();
+  kvp.insert(key, value);
+  kvp
+}
+
+#[test]
+fn _0001() {
+  // Default options write the same output as `to_kivi_string`.
+  for input in [DATA_PROPERTIES, DATA_002, DATA_005, DATA_TASKS] {
+    let kvp = kivi::load_from_string(input);
+    assert_eq!(kvp.to_kivi_string().unwrap(), WriterOptions::new().format(&kvp).unwrap());
+    assert_eq!(WriterOptions::new(), WriterOptions::default());
+  }
+}
+
+#[test]
+fn _0002() {
+  // The first marker that keeps the text unchanged is chosen.
+  let options = WriterOptions::new().markers(&['@', '~', '^']);
+  assert_eq!("a\n@b\nc@\n", options.format(&kvp("a", "b\nc")).unwrap());
+  assert_eq!("a\n~b@\nc~\n", options.format(&kvp("a", "b@\nc")).unwrap());
+  assert_eq!("a\n^b@\n~\nc^\n", options.format(&kvp("a", "b@\n~\nc")).unwrap());
+  assert_eq!("@x~y@\nb\n", options.format(&kvp("x~y", "b")).unwrap());
+}
+
+#[test]
+fn _0003() {
+  // Error is reported when no marker works.
+  let options = WriterOptions::new().markers(&['@', '~']);
+  assert_eq!(&ErrorKind::Unrepresentable, options.format(&kvp("a", "@\n~\n")).unwrap_err().kind());
+  let options = WriterOptions::new().markers(&[]);
+  assert_eq!("a\nb\n", options.format(&kvp("a", "b")).unwrap());
+  assert_eq!(&ErrorKind::Unrepresentable, options.format(&kvp("a", " b")).unwrap_err().kind());
+  let options = WriterOptions::new().markers(&['\n', '\r']);
+  assert_eq!(&ErrorKind::Unrepresentable, options.format(&kvp("a", "b\nc")).unwrap_err().kind());
+}
+
+#[test]
+fn _0004() {
+  // All short texts built from characters significant for the parser are written back unchanged.
+  let markers = ['"', '~'];
+  let alphabet = ['a', ' ', '\n', '"', '~'];
+  let mut texts = vec![String::new()];
+  for _ in 0..4 {
+    let longer = texts
+      .iter()
+      .flat_map(|text| alphabet.iter().map(move |ch| format!("{}{}", text, ch)))
+      .collect::<Vec<String>>();
+    texts.extend(longer);
+  }
+  texts.sort();
+  texts.dedup();
+  let options = WriterOptions::new().markers(&markers);
+  for text in &texts {
+    let kvp = kvp(text, text);
+    match options.format(&kvp) {
+      Ok(output) => assert_eq!(kvp, load_from_string_markers(&output, &markers), "{:?}", output),
+      Err(error) => {
+        assert_eq!(&ErrorKind::Unrepresentable, error.kind());
+        assert!(text.contains("\"\n") && text.contains("~\n"), "{:?}", text);
+      }
+    }
+  }
+}
+
+#[test]
+fn _0005() {
+  // Writing to writer.
+  let options = WriterOptions::new().markers(&['@', '~']);
+  let kvp = kvp("a@\nb", "c");
+  let mut output = vec![];
+  options.write(&kvp, &mut output).unwrap();
+  assert_eq!(b"~a@\nb~\nc\n", output.as_slice());
+}
+
+#[test]
+fn _0006() {
+  // Separate markers for keys and values.
+  let options = WriterOptions::new().key_markers(&['@']).value_markers(&['"', '~']);
+  assert_eq!("a\"b\n\"c@\nd\"\n", options.format(&kvp("a\"b", "c@\nd")).unwrap());
+  assert_eq!("@a\"\nb@\nc@d\n", options.format(&kvp("a\"\nb", "c@d")).unwrap());
+  assert_eq!(ErrorKind::Unrepresentable, *options.format(&kvp("a@\nb", "c")).unwrap_err().kind());
+  let kvp = kvp("a\"\"\nb", "~c\"\n");
+  let output = options.format(&kvp).unwrap();
+  assert_eq!(kvp, LoaderOptions::new().key_markers(&['@']).value_markers(&['"', '~']).load_str(&output).unwrap());
+}
+
+#[test]
+fn _0007() {
+  // Default output uses LF, ends with a line ending and has no byte order mark and no blank lines.
+  let mut kvp = kvp("a", "b\nc");
+  kvp.insert("d", "e");
+  let default = WriterOptions::new().format(&kvp).unwrap();
+  assert_eq!("a\n\"b\nc\"\nd\ne\n", default);
+  let options = WriterOptions::new().line_ending(LineEnding::Lf).final_newline(true).bom(false).blank_lines(0);
+  assert_eq!(default, options.format(&kvp).unwrap());
+}
+
+#[test]
+fn _0008() {
+  // Output for Windows tooling uses CRLF and starts with the byte order mark.
+  let mut kvp = kvp("a", "b\nc");
+  kvp.insert("d", "e");
+  let options = WriterOptions::new().line_ending(LineEnding::CrLf).bom(true);
+  let output = options.format(&kvp).unwrap();
+  assert_eq!("\u{FEFF}a\r\n\"b\r\nc\"\r\nd\r\ne\r\n", output);
+  assert_eq!(kvp, load_from_string(&output));
+  let mut bytes = vec![];
+  options.write(&kvp, &mut bytes).unwrap();
+  assert_eq!(output.as_bytes(), bytes.as_slice());
+  assert_eq!(kvp, LoaderOptions::new().load_reader(bytes.as_slice()).unwrap());
+  let document = Document::parse(&output);
+  assert_eq!(LineEnding::CrLf, document.line_ending());
+  assert_eq!(output, document.to_string());
+}
+
+#[test]
+fn _0009() {
+  // Blank lines between key-value pairs and the final line ending.
+  let mut kvp = kvp("a", "b");
+  kvp.insert("c", "d");
+  kvp.insert("e", "f");
+  let options = WriterOptions::new().blank_lines(2).final_newline(false);
+  let output = options.format(&kvp).unwrap();
+  assert_eq!("a\nb\n\n\nc\nd\n\n\ne\nf", output);
+  assert_eq!(kvp, load_from_string(&output));
+  assert_eq!("", options.format(&KeyValuePairs::new()).unwrap());
+  assert_eq!("\u{FEFF}", options.bom(true).format(&KeyValuePairs::new()).unwrap());
+}
+
+#[test]
+fn _0010() {
+  // The last value enclosed in delimiters is always followed by a line ending, required to close it.
+  let options = WriterOptions::new().final_newline(false).line_ending(LineEnding::Cr);
+  let output = options.format(&kvp("a", "b\nc")).unwrap();
+  assert_eq!("a\r\"b\rc\"\r", output);
+  assert_eq!(kvp("a", "b\nc"), load_from_string(&output));
+}